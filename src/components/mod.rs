@@ -2,7 +2,9 @@ use rltk::RGB;
 use serde::{Deserialize, Serialize};
 use specs::error::NoError;
 use specs::prelude::*;
-use specs::saveload::{ConvertSaveload, Marker};
+use specs::saveload::{ConvertSaveload, MarkedBuilder, Marker, SimpleMarker};
+use specs::storage::MaskedStorage;
+use std::ops::Deref;
 
 #[derive(Component, ConvertSaveload, Clone, Copy)]
 pub struct Position {
@@ -34,6 +36,69 @@ pub struct Viewshed {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Monster {}
 
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Resting {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Dead {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ItemUser {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct PackLeader {}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct PackFollower {
+    pub leader: Entity,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Faction {
+    pub name: String,
+}
+
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum AiState {
+    Sleep,
+    Wander,
+    Hunt,
+    Search,
+    Flee,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Ai {
+    pub state: AiState,
+    pub last_seen_target: Option<rltk::Point>,
+    pub destination: Option<rltk::Point>,
+}
+
+impl Default for Ai {
+    fn default() -> Ai {
+        Ai {
+            state: AiState::Wander,
+            last_seen_target: None,
+            destination: None,
+        }
+    }
+}
+
+impl Ai {
+    pub fn transition(&mut self, state: AiState, entity: Entity, name: Option<&Name>) {
+        if self.state != state {
+            rltk::console::log(format!(
+                "AI: {} ({}) {:?} -> {:?}",
+                name.map_or("?", |name| &name.name),
+                entity.id(),
+                self.state,
+                state
+            ));
+            self.state = state;
+        }
+    }
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct Name {
     pub name: String,
@@ -50,6 +115,182 @@ pub struct CombatStats {
     pub power: i32,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct Attribute {
+    pub base: i32,
+    pub modifiers: i32,
+    pub bonus: i32,
+}
+
+impl Attribute {
+    pub fn new(base: i32) -> Attribute {
+        Attribute {
+            base,
+            modifiers: 0,
+            bonus: (base - 10) / 2,
+        }
+    }
+
+    pub fn total(&self) -> i32 {
+        self.base + self.modifiers
+    }
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Attributes {
+    pub might: Attribute,
+    pub fitness: Attribute,
+    pub quickness: Attribute,
+    pub intelligence: Attribute,
+    pub dirty: bool,
+}
+
+impl Attributes {
+    pub fn new(might: i32, fitness: i32, quickness: i32, intelligence: i32) -> Attributes {
+        Attributes {
+            might: Attribute::new(might),
+            fitness: Attribute::new(fitness),
+            quickness: Attribute::new(quickness),
+            intelligence: Attribute::new(intelligence),
+            dirty: true,
+        }
+    }
+
+    pub fn invalidate(attributes: &mut WriteStorage<Attributes>, entity: Entity) {
+        if let Some(attributes) = attributes.get_mut(entity) {
+            attributes.dirty = true;
+        }
+    }
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct DerivedStats {
+    pub to_hit: i32,
+    pub carry_capacity: i32,
+    pub initiative: i32,
+    pub stealth: i32,
+    pub regen_interval: i32,
+}
+
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum HungerState {
+    WellFed,
+    Normal,
+    Hungry,
+    Starving,
+}
+
+impl HungerState {
+    pub fn name(self) -> &'static str {
+        match self {
+            HungerState::WellFed => "Well Fed",
+            HungerState::Normal => "Normal",
+            HungerState::Hungry => "Hungry",
+            HungerState::Starving => "Starving",
+        }
+    }
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct HungerClock {
+    pub state: HungerState,
+    pub duration: i32,
+}
+
+impl HungerClock {
+    pub const WELL_FED_DURATION: i32 = 300;
+    pub const STAGE_DURATION: i32 = 200;
+    pub const STARVATION_INTERVAL: i32 = 10;
+
+    pub fn new() -> Self {
+        HungerClock {
+            state: HungerState::WellFed,
+            duration: HungerClock::WELL_FED_DURATION,
+        }
+    }
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Burden {
+    pub carried: i32,
+    pub capacity: i32,
+}
+
+impl Burden {
+    pub fn overburdened(&self) -> bool {
+        self.carried > self.capacity
+    }
+}
+
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum DamageType {
+    Bludgeoning,
+    Slashing,
+    Piercing,
+    Fire,
+    Cold,
+    Poison,
+    Starvation,
+}
+
+impl DamageType {
+    pub fn name(self) -> &'static str {
+        match self {
+            DamageType::Bludgeoning => "bludgeoning",
+            DamageType::Slashing => "slashing",
+            DamageType::Piercing => "piercing",
+            DamageType::Fire => "fire",
+            DamageType::Cold => "cold",
+            DamageType::Poison => "poison",
+            DamageType::Starvation => "starvation",
+        }
+    }
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Energy {
+    pub energy: i32,
+    pub speed: i32,
+}
+
+impl Energy {
+    pub const ACTION_COST: i32 = 100;
+    pub const MOVE_COST: i32 = 100;
+    pub const ATTACK_COST: i32 = 100;
+    pub const USE_ITEM_COST: i32 = 100;
+    pub const EQUIP_COST: i32 = 150;
+    pub const PICKUP_COST: i32 = 100;
+    pub const DROP_COST: i32 = 50;
+    pub const THROW_COST: i32 = 100;
+
+    pub fn new(speed: i32) -> Energy {
+        Energy { energy: 0, speed }
+    }
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct MyTurn {}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct MeleeAttack {
+    pub damage_n_dice: i32,
+    pub damage_die_type: i32,
+    pub damage_bonus: i32,
+    pub damage_type: DamageType,
+}
+
+impl MeleeAttack {
+    pub fn new(damage: &str, damage_type: DamageType) -> MeleeAttack {
+        let dice = rltk::parse_dice_string(damage).expect("Invalid damage dice");
+        MeleeAttack {
+            damage_n_dice: dice.n_dice,
+            damage_die_type: dice.die_type,
+            damage_bonus: dice.bonus,
+            damage_type,
+        }
+    }
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct WantsToMelee {
     pub target: Entity,
@@ -57,11 +298,46 @@ pub struct WantsToMelee {
 
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct SufferDamage {
-    pub amount: i32,
+    pub amount: Vec<(i32, DamageType)>,
+}
+
+impl SufferDamage {
+    pub fn new_damage(store: &mut WriteStorage<SufferDamage>, victim: Entity, amount: i32, damage_type: DamageType) {
+        if let Some(suffering) = store.get_mut(victim) {
+            suffering.amount.push((amount, damage_type));
+        } else {
+            store
+                .insert(
+                    victim,
+                    SufferDamage {
+                        amount: vec![(amount, damage_type)],
+                    },
+                )
+                .expect("Unable to insert damage");
+        }
+    }
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Resistant {
+    pub damage_types: Vec<DamageType>,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Vulnerable {
+    pub damage_types: Vec<DamageType>,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Immune {
+    pub damage_types: Vec<DamageType>,
 }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct Item {}
+pub struct Item {
+    pub weight: i32,
+    pub value: i32,
+}
 
 #[derive(Component, Debug, Clone, ConvertSaveload)]
 pub struct InBackpack {
@@ -82,6 +358,63 @@ pub struct WantsToDropItem {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Consumable {}
 
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Stackable {
+    pub quantity: i32,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Gold {
+    pub amount: i32,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Purse {
+    pub gold: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Vendor {}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Charges {
+    pub current: i32,
+    pub max: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesRecharge {}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct ObfuscatedName {
+    pub name: String,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesIdentification {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesFood {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Shatters {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesMagicMapping {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesRemoveCurse {}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Cursed {
+    pub revealed: bool,
+    pub power_penalty: i32,
+    pub defense_penalty: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesTeleport {}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct ProvidesHealing {
     pub heal_amount: i32,
@@ -95,6 +428,7 @@ pub struct Ranged {
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct InflictsDamage {
     pub damage: i32,
+    pub damage_type: DamageType,
 }
 
 #[derive(Component, Debug, Clone, ConvertSaveload)]
@@ -103,15 +437,178 @@ pub struct WantsToUseItem {
     pub target: Option<rltk::Point>,
 }
 
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToThrowItem {
+    pub item: Entity,
+    pub target: rltk::Point,
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct AreaOfEffect {
     pub radius: i32,
 }
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Ability {
+    pub owner: Entity,
+    pub cooldown: i32,
+    pub turns_until_ready: i32,
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct Confusion {
     pub turns: i32,
 }
 
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum StatusEffectType {
+    Confused,
+    Poisoned,
+    Regenerating,
+    Slowed,
+    Stunned,
+}
+
+impl StatusEffectType {
+    pub fn name(self) -> &'static str {
+        match self {
+            StatusEffectType::Confused => "confused",
+            StatusEffectType::Poisoned => "poisoned",
+            StatusEffectType::Regenerating => "regenerating",
+            StatusEffectType::Slowed => "slowed",
+            StatusEffectType::Stunned => "stunned",
+        }
+    }
+
+    pub fn attribute_modifiers(self) -> AttributeBonus {
+        match self {
+            StatusEffectType::Poisoned => AttributeBonus {
+                might: -2,
+                ..AttributeBonus::default()
+            },
+            _ => AttributeBonus::default(),
+        }
+    }
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct InflictsStatus {
+    pub effect_type: StatusEffectType,
+    pub turns: i32,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct StatusEffect {
+    pub target: Entity,
+    pub effect_type: StatusEffectType,
+    pub turns: i32,
+}
+
+impl StatusEffect {
+    pub fn find<'a, D>(
+        effects: &'a Storage<StatusEffect, D>,
+        target: Entity,
+        effect_type: StatusEffectType,
+    ) -> Option<&'a StatusEffect>
+    where
+        D: Deref<Target = MaskedStorage<StatusEffect>>,
+    {
+        effects
+            .join()
+            .find(|effect| effect.target == target && effect.effect_type == effect_type)
+    }
+
+    pub fn is_affected<D>(effects: &Storage<StatusEffect, D>, target: Entity, effect_type: StatusEffectType) -> bool
+    where
+        D: Deref<Target = MaskedStorage<StatusEffect>>,
+    {
+        Self::find(effects, target, effect_type).is_some()
+    }
+
+    pub fn add(
+        lazy: &LazyUpdate,
+        entities: &Entities,
+        effects: &mut WriteStorage<StatusEffect>,
+        target: Entity,
+        effect_type: StatusEffectType,
+        turns: i32,
+    ) {
+        if let Some(effect) = effects
+            .join()
+            .find(|effect| effect.target == target && effect.effect_type == effect_type)
+        {
+            effect.turns = i32::max(effect.turns, turns);
+        } else {
+            let effect = lazy
+                .create_entity(entities)
+                .marked::<SimpleMarker<SerializeMe>>()
+                .build();
+            effects
+                .insert(
+                    effect,
+                    StatusEffect {
+                        target,
+                        effect_type,
+                        turns,
+                    },
+                )
+                .expect("Unable to insert status effect");
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum EquipmentSlot {
+    Melee,
+    Shield,
+    Head,
+    Body,
+    Ring,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Equippable {
+    pub slot: EquipmentSlot,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Equipped {
+    pub owner: Entity,
+    pub slot: EquipmentSlot,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct MeleePowerBonus {
+    pub power: i32,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct DefenseBonus {
+    pub defense: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AttributeBonus {
+    pub might: i32,
+    pub fitness: i32,
+    pub quickness: i32,
+    pub intelligence: i32,
+}
+
+impl AttributeBonus {
+    pub fn add(&mut self, other: &AttributeBonus) {
+        self.might += other.might;
+        self.fitness += other.fitness;
+        self.quickness += other.quickness;
+        self.intelligence += other.intelligence;
+    }
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToRemoveItem {
+    pub item: Entity,
+}
+
 pub struct SerializeMe {}
 
 #[derive(Component, Serialize, Deserialize, Clone)]
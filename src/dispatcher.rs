@@ -0,0 +1,99 @@
+use crate::systems::{
+    AchievementSystem, AttributeSystem, DamageSystem, EffectSystem, EncumbranceSystem, EventLogSystem, HearingSystem,
+    HungerSystem, InitiativeSystem, ItemCollectionSystem, ItemDropSystem, ItemRemoveSystem, ItemUseSystem,
+    MapIndexingSystem, MeleeCombatSystem, MonsterAI, RegenerationSystem, SoundSystem, StatisticsSystem,
+    StatusEffectSystem, ThrowSystem, VisibilitySystem,
+};
+use specs::prelude::*;
+
+pub trait UnifiedDispatcher {
+    fn run_now(&mut self, ecs: &World);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct MultiThreadedDispatcher {
+    dispatcher: Dispatcher<'static, 'static>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl UnifiedDispatcher for MultiThreadedDispatcher {
+    fn run_now(&mut self, ecs: &World) {
+        self.dispatcher.dispatch(ecs);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub struct SingleThreadedDispatcher {
+    systems: Vec<Box<dyn for<'a> RunNow<'a>>>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl UnifiedDispatcher for SingleThreadedDispatcher {
+    fn run_now(&mut self, ecs: &World) {
+        for system in self.systems.iter_mut() {
+            system.run_now(ecs);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+macro_rules! construct_dispatcher {
+    ( $( ($system:expr, $name:expr, $deps:expr) ),* $(,)? ) => {{
+        let dispatcher = DispatcherBuilder::new()
+            $( .with($system, $name, $deps) )*
+            .build();
+        Box::new(MultiThreadedDispatcher { dispatcher })
+    }};
+}
+
+#[cfg(target_arch = "wasm32")]
+macro_rules! construct_dispatcher {
+    ( $( ($system:expr, $name:expr, $deps:expr) ),* $(,)? ) => {{
+        let systems: Vec<Box<dyn for<'a> RunNow<'a>>> = vec![ $( Box::new($system) ),* ];
+        Box::new(SingleThreadedDispatcher { systems })
+    }};
+}
+
+// This is the single place that orders the turn. Each system names the systems
+// it must run after; specs runs independent ones in parallel. wasm has no
+// threads, so there the systems run one at a time in listing order. Keep every
+// system listed after its dependencies.
+pub fn new_turn_dispatcher() -> Box<dyn UnifiedDispatcher + 'static> {
+    construct_dispatcher!(
+        (VisibilitySystem {}, "visibility", &[]),
+        (AttributeSystem {}, "attributes", &[]),
+        (EncumbranceSystem {}, "encumbrance", &["attributes"]),
+        (InitiativeSystem {}, "initiative", &["attributes", "encumbrance"]),
+        (StatusEffectSystem {}, "status_effects", &["initiative"]),
+        (HungerSystem {}, "hunger", &["initiative"]),
+        (RegenerationSystem {}, "regeneration", &["hunger"]),
+        (HearingSystem {}, "hearing", &["initiative"]),
+        (
+            MonsterAI {},
+            "monster_ai",
+            &["visibility", "status_effects", "hunger", "hearing"]
+        ),
+        (MapIndexingSystem {}, "map_indexing", &["monster_ai"]),
+        (ThrowSystem {}, "throw", &["map_indexing"]),
+        (MeleeCombatSystem {}, "melee_combat", &["map_indexing"]),
+        (ItemCollectionSystem {}, "item_collection", &["monster_ai"]),
+        (ItemUseSystem {}, "item_use", &["throw", "item_collection"]),
+        (
+            EffectSystem {},
+            "effects",
+            &["item_use", "status_effects", "hunger", "regeneration"]
+        ),
+        (DamageSystem {}, "damage", &["melee_combat", "effects"]),
+        (ItemDropSystem {}, "item_drop", &["item_use"]),
+        (ItemRemoveSystem {}, "item_remove", &["item_use"]),
+    )
+}
+
+pub fn new_event_dispatcher() -> Box<dyn UnifiedDispatcher + 'static> {
+    construct_dispatcher!(
+        (EventLogSystem {}, "event_log", &[]),
+        (StatisticsSystem {}, "statistics", &[]),
+        (AchievementSystem {}, "achievements", &["event_log"]),
+        (SoundSystem {}, "sound", &[]),
+    )
+}
@@ -0,0 +1,47 @@
+use crate::components::{DamageType, StatusEffectType};
+use rltk::Point;
+use specs::prelude::*;
+use std::collections::VecDeque;
+
+#[derive(Clone, Copy)]
+pub enum EffectType {
+    Damage { amount: i32, damage_type: DamageType },
+    Healing { amount: i32 },
+    Status { effect_type: StatusEffectType, turns: i32 },
+    WellFed,
+    Identify,
+    Recharge,
+    RemoveCurse,
+    MagicMapping,
+    Teleport,
+    ItemUse { item: Entity },
+}
+
+#[derive(Clone)]
+pub enum Targets {
+    Single { target: Entity },
+    Tile { tile: Point },
+    Area { tiles: Vec<Point> },
+    Entities { targets: Vec<Entity> },
+}
+
+pub struct Effect {
+    pub creator: Option<Entity>,
+    pub effect_type: EffectType,
+    pub targets: Targets,
+}
+
+#[derive(Default)]
+pub struct EffectQueue {
+    pub effects: VecDeque<Effect>,
+}
+
+impl EffectQueue {
+    pub fn add_effect(&mut self, creator: Option<Entity>, effect_type: EffectType, targets: Targets) {
+        self.effects.push_back(Effect {
+            creator,
+            effect_type,
+            targets,
+        });
+    }
+}
@@ -0,0 +1,32 @@
+use crate::components::DamageType;
+use specs::prelude::*;
+
+pub enum GameEvent {
+    EntityDamaged {
+        entity: Entity,
+        amount: i32,
+        damage_type: DamageType,
+    },
+    EntityDied {
+        entity: Entity,
+        name: String,
+    },
+    ItemPickedUp {
+        collector: Entity,
+        item: Entity,
+    },
+    LevelEntered {
+        depth: i32,
+    },
+}
+
+#[derive(Default)]
+pub struct GameEvents {
+    pub events: Vec<GameEvent>,
+}
+
+impl GameEvents {
+    pub fn publish(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum Reaction {
+    Attack,
+    Ignore,
+    Flee,
+}
+
+pub const PLAYER: &str = "Player";
+pub const ORCS: &str = "Orcs";
+pub const GOBLINS: &str = "Goblins";
+pub const ANIMALS: &str = "Animals";
+pub const ELEMENTALS: &str = "Elementals";
+pub const TOWNSFOLK: &str = "Townsfolk";
+
+struct FactionReactions {
+    default: Reaction,
+    towards: HashMap<&'static str, Reaction>,
+}
+
+pub struct FactionTable {
+    factions: HashMap<&'static str, FactionReactions>,
+}
+
+impl Default for FactionTable {
+    fn default() -> FactionTable {
+        let mut table = FactionTable {
+            factions: HashMap::new(),
+        };
+        table.add(PLAYER, Reaction::Attack, &[]);
+        table.add(
+            ORCS,
+            Reaction::Attack,
+            &[
+                (ORCS, Reaction::Ignore),
+                (ANIMALS, Reaction::Ignore),
+                (ELEMENTALS, Reaction::Flee),
+            ],
+        );
+        table.add(
+            GOBLINS,
+            Reaction::Attack,
+            &[
+                (GOBLINS, Reaction::Ignore),
+                (ANIMALS, Reaction::Ignore),
+                (ELEMENTALS, Reaction::Flee),
+            ],
+        );
+        table.add(ANIMALS, Reaction::Ignore, &[]);
+        table.add(ELEMENTALS, Reaction::Attack, &[(ELEMENTALS, Reaction::Ignore)]);
+        table.add(
+            TOWNSFOLK,
+            Reaction::Flee,
+            &[
+                (PLAYER, Reaction::Ignore),
+                (TOWNSFOLK, Reaction::Ignore),
+                (ANIMALS, Reaction::Ignore),
+            ],
+        );
+        table
+    }
+}
+
+impl FactionTable {
+    fn add(&mut self, faction: &'static str, default: Reaction, towards: &[(&'static str, Reaction)]) {
+        self.factions.insert(
+            faction,
+            FactionReactions {
+                default,
+                towards: towards.iter().cloned().collect(),
+            },
+        );
+    }
+
+    pub fn reaction(&self, my_faction: &str, their_faction: &str) -> Reaction {
+        match self.factions.get(my_faction) {
+            None => Reaction::Ignore,
+            Some(reactions) => *reactions.towards.get(their_faction).unwrap_or(&reactions.default),
+        }
+    }
+}
@@ -1,10 +1,20 @@
-use crate::components::{CombatStats, InBackpack, Name, Player, Position, Viewshed};
+use crate::components::{
+    Attributes, Burden, Charges, CombatStats, Cursed, DerivedStats, Equipped, HungerClock, HungerState, InBackpack,
+    Item, Name, ObfuscatedName, Player, Position, Purse, Stackable, Viewshed,
+};
 use crate::game_log::GameLog;
+use crate::identification::Identification;
+use crate::map::Map;
+use crate::projectile::Projectiles;
 use crate::state::State;
+use crate::statistics::Statistics;
 use crate::{MAP_HEIGHT, MAP_WIDTH, UI_HEIGHT};
 use rltk::{Console, Point, Rltk, VirtualKeyCode, RGB};
 use specs::prelude::*;
 
+const PROJECTILE_MS_PER_TILE: f32 = 30.0;
+const MAX_MENU_ITEMS: usize = 26;
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum ItemMenuResult {
     Cancel,
@@ -12,6 +22,18 @@ pub enum ItemMenuResult {
     Selected,
 }
 #[derive(PartialEq, Copy, Clone)]
+pub enum VendorMode {
+    Buy,
+    Sell,
+}
+#[derive(PartialEq, Copy, Clone)]
+pub enum VendorResult {
+    Cancel,
+    NoResponse,
+    ToggleMode,
+    Selected,
+}
+#[derive(PartialEq, Copy, Clone)]
 pub enum MainMenuSelection {
     NewGame,
     LoadGame,
@@ -35,6 +57,16 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
 
     let combat_stats = ecs.read_storage::<CombatStats>();
     let players = ecs.read_storage::<Player>();
+    let purses = ecs.read_storage::<Purse>();
+    for (_player, purse) in (&players, &purses).join() {
+        ctx.print_color(
+            1,
+            MAP_HEIGHT,
+            RGB::named(rltk::GOLD),
+            RGB::named(rltk::BLACK),
+            &format!(" Gold: {} ", purse.gold),
+        );
+    }
     for (_player, stats) in (&players, &combat_stats).join() {
         let health = format!(" HP: {} / {}", stats.hp, stats.max_hp);
         ctx.print_color(
@@ -47,7 +79,7 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         ctx.draw_bar_horizontal(
             28,
             MAP_HEIGHT,
-            51,
+            40,
             stats.hp,
             stats.max_hp,
             RGB::named(rltk::RED),
@@ -55,6 +87,17 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         )
     }
 
+    let hunger_clocks = ecs.read_storage::<HungerClock>();
+    for (_player, clock) in (&players, &hunger_clocks).join() {
+        let colour = match clock.state {
+            HungerState::WellFed => RGB::named(rltk::GREEN),
+            HungerState::Normal => continue,
+            HungerState::Hungry => RGB::named(rltk::ORANGE),
+            HungerState::Starving => RGB::named(rltk::RED),
+        };
+        ctx.print_color(70, MAP_HEIGHT, colour, RGB::named(rltk::BLACK), clock.state.name());
+    }
+
     let log = ecs.fetch::<GameLog>();
 
     let mut y = MAP_HEIGHT + 1;
@@ -71,13 +114,16 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
 fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     // TODO: MAKE TOOLTIPS VISIBLE ONLY WHEN POINTING TO VISIBLE TILE
     let names = ecs.read_storage::<Name>();
+    let obfuscated_names = ecs.read_storage::<ObfuscatedName>();
+    let identification = ecs.fetch::<Identification>();
     let positions = ecs.read_storage::<Position>();
+    let entities = ecs.entities();
     let mouse_pos = ctx.mouse_pos();
     if mouse_pos.0 <= MAP_WIDTH || mouse_pos.1 <= MAP_HEIGHT {
         let mut tooltip = Vec::new();
-        for (name, position) in (&names, &positions).join() {
+        for (entity, _name, position) in (&entities, &names, &positions).join() {
             if position.x == mouse_pos.0 && position.y == mouse_pos.1 {
-                tooltip.push(name.name.to_string());
+                tooltip.push(identification.display_name(entity, &names, &obfuscated_names));
             }
         }
 
@@ -142,20 +188,69 @@ fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     }
 }
 
-pub fn draw_inventory_menu(state: &mut State, ctx: &mut Rltk, title: &str) -> (ItemMenuResult, Option<Entity>) {
-    let white = RGB::named(rltk::WHITE);
-    let black = RGB::named(rltk::BLACK);
-    let yellow = RGB::named(rltk::YELLOW);
+fn plural(name: &str) -> String {
+    match name.find(" labelled ") {
+        Some(idx) => format!("{}s{}", &name[..idx], &name[idx..]),
+        None => format!("{}s", name),
+    }
+}
 
-    let player_entity = state.ecs.fetch::<Entity>();
-    let names = state.ecs.read_storage::<Name>();
-    let in_backpack = state.ecs.read_storage::<InBackpack>();
-    let entities = state.ecs.entities();
+fn backpack_items(ecs: &World, owner: Entity) -> Vec<(Entity, String)> {
+    let names = ecs.read_storage::<Name>();
+    let obfuscated_names = ecs.read_storage::<ObfuscatedName>();
+    let identification = ecs.fetch::<Identification>();
+    let stackables = ecs.read_storage::<Stackable>();
+    let charges = ecs.read_storage::<Charges>();
+    let cursed = ecs.read_storage::<Cursed>();
+    let in_backpack = ecs.read_storage::<InBackpack>();
+    let entities = ecs.entities();
+
+    (&in_backpack, &entities, &names)
+        .join()
+        .filter(|item| item.0.owner == owner)
+        .map(|(_in_backpack, entity, _name)| {
+            let name = identification.display_name(entity, &names, &obfuscated_names);
+            match (stackables.get(entity), charges.get(entity)) {
+                (Some(stack), _) if stack.quantity > 1 => (entity, format!("{} {}", stack.quantity, plural(&name))),
+                (_, Some(charges)) => (entity, format!("{} ({}/{})", name, charges.current, charges.max)),
+                _ if matches!(cursed.get(entity), Some(curse) if curse.revealed) => {
+                    (entity, format!("{} (cursed)", name))
+                }
+                _ => (entity, name),
+            }
+        })
+        .collect()
+}
+
+fn equipped_items(ecs: &World) -> Vec<(Entity, String)> {
+    let player_entity = ecs.fetch::<Entity>();
+    let names = ecs.read_storage::<Name>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let cursed = ecs.read_storage::<Cursed>();
+    let entities = ecs.entities();
 
-    let count = (&in_backpack, &names)
+    (&equipped, &entities, &names)
         .join()
         .filter(|item| item.0.owner == *player_entity)
-        .count() as i32;
+        .map(|(_equipped, entity, name)| match cursed.get(entity) {
+            Some(curse) if curse.revealed => (entity, format!("{} (equipped, cursed)", name.name)),
+            _ => (entity, format!("{} (equipped)", name.name)),
+        })
+        .collect()
+}
+
+pub fn draw_inventory_menu(
+    ctx: &mut Rltk,
+    title: &str,
+    items: &[(Entity, String)],
+) -> (ItemMenuResult, Option<Entity>) {
+    let white = RGB::named(rltk::WHITE);
+    let black = RGB::named(rltk::BLACK);
+    let yellow = RGB::named(rltk::YELLOW);
+
+    let hidden = items.len().saturating_sub(MAX_MENU_ITEMS);
+    let items = &items[..items.len() - hidden];
+    let count = items.len() as i32;
 
     let y = 10;
     let x = 10;
@@ -163,20 +258,21 @@ pub fn draw_inventory_menu(state: &mut State, ctx: &mut Rltk, title: &str) -> (I
     ctx.draw_box(x, y, 31, count + 3, white, black);
     ctx.print_color(x + 5, y, yellow, black, title);
     ctx.print_color(x + 5, y + count + 3, yellow, black, "ESCAPE to cancel");
+    if hidden > 0 {
+        ctx.print_color(
+            x + 5,
+            y + count + 2,
+            yellow,
+            black,
+            &format!("({} more not shown)", hidden),
+        );
+    }
 
-    let mut equippable = Vec::new();
-
-    for (i, (_in_backpack, entitiy, name)) in (&in_backpack, &entities, &names)
-        .join()
-        .filter(|item| item.0.owner == *player_entity)
-        .enumerate()
-    {
+    for (i, (_entity, name)) in items.iter().enumerate() {
         ctx.set(x + 1, y + 2 + i as i32, white, black, rltk::to_cp437('('));
         ctx.set(x + 2, y + 2 + i as i32, yellow, black, 97 + i as u8);
         ctx.set(x + 3, y + 2 + i as i32, white, black, rltk::to_cp437(')'));
-        ctx.print(x + 5, y + 2 + i as i32, &name.name);
-
-        equippable.push(entitiy);
+        ctx.print(x + 5, y + 2 + i as i32, name);
     }
 
     match ctx.key {
@@ -185,7 +281,7 @@ pub fn draw_inventory_menu(state: &mut State, ctx: &mut Rltk, title: &str) -> (I
         Some(key) => {
             let selection = rltk::letter_to_option(key);
             if selection > -1 && selection < count {
-                (ItemMenuResult::Selected, Some(equippable[selection as usize]))
+                (ItemMenuResult::Selected, Some(items[selection as usize].0))
             } else {
                 (ItemMenuResult::NoResponse, None)
             }
@@ -194,11 +290,158 @@ pub fn draw_inventory_menu(state: &mut State, ctx: &mut Rltk, title: &str) -> (I
 }
 
 pub fn draw_drop_item_menu(state: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
-    draw_inventory_menu(state, ctx, "Drop Which Item?")
+    let items = backpack_items(&state.ecs, *state.ecs.fetch::<Entity>());
+    draw_inventory_menu(ctx, "Drop Which Item?", &items)
+}
+
+pub fn draw_throw_item_menu(state: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
+    let items = backpack_items(&state.ecs, *state.ecs.fetch::<Entity>());
+    draw_inventory_menu(ctx, "Throw Which Item?", &items)
+}
+
+pub fn trade_price(item: &Item, mode: VendorMode) -> i32 {
+    match mode {
+        VendorMode::Buy => item.value,
+        VendorMode::Sell => item.value / 2,
+    }
+}
+
+pub fn draw_vendor_menu(
+    state: &mut State,
+    ctx: &mut Rltk,
+    vendor: Entity,
+    mode: VendorMode,
+) -> (VendorResult, Option<Entity>) {
+    if ctx.key == Some(VirtualKeyCode::Tab) {
+        return (VendorResult::ToggleMode, None);
+    }
+
+    let player_entity = *state.ecs.fetch::<Entity>();
+    let owner = match mode {
+        VendorMode::Buy => vendor,
+        VendorMode::Sell => player_entity,
+    };
+    let gold = state
+        .ecs
+        .read_storage::<Purse>()
+        .get(player_entity)
+        .map_or(0, |purse| purse.gold);
+    let (title, hint) = match mode {
+        VendorMode::Buy => (format!("Buy ({} gold)", gold), "TAB to sell"),
+        VendorMode::Sell => (format!("Sell ({} gold)", gold), "TAB to buy"),
+    };
+
+    let items: Vec<(Entity, String)> = {
+        let item_stats = state.ecs.read_storage::<Item>();
+        backpack_items(&state.ecs, owner)
+            .into_iter()
+            .map(|(entity, name)| {
+                let price = item_stats.get(entity).map_or(0, |item| trade_price(item, mode));
+                (entity, format!("{} - {} gold", name, price))
+            })
+            .collect()
+    };
+
+    ctx.print_color(
+        15,
+        14 + items.len() as i32,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        hint,
+    );
+    match draw_inventory_menu(ctx, &title, &items) {
+        (ItemMenuResult::Cancel, _) => (VendorResult::Cancel, None),
+        (ItemMenuResult::Selected, item) => (VendorResult::Selected, item),
+        (ItemMenuResult::NoResponse, _) => (VendorResult::NoResponse, None),
+    }
+}
+
+pub fn draw_projectiles(ecs: &World, ctx: &mut Rltk) {
+    let map = ecs.fetch::<Map>();
+    let mut projectiles = ecs.write_resource::<Projectiles>();
+    for flight in projectiles.flights.iter_mut() {
+        let step = (flight.elapsed_ms / PROJECTILE_MS_PER_TILE) as usize;
+        if let Some(point) = flight.path.get(step) {
+            if map.visible_tiles[map.point_to_idx(*point)] {
+                ctx.set(point.x, point.y, flight.fg, RGB::named(rltk::BLACK), flight.glyph);
+            }
+        }
+        flight.elapsed_ms += ctx.frame_time_ms;
+    }
+    projectiles
+        .flights
+        .retain(|flight| ((flight.elapsed_ms / PROJECTILE_MS_PER_TILE) as usize) < flight.path.len());
 }
 
 pub fn draw_show_inventory_item_menu(state: &mut State, ctx: &mut Rltk) -> (ItemMenuResult, Option<Entity>) {
-    draw_inventory_menu(state, ctx, "Inventory")
+    let mut items = equipped_items(&state.ecs);
+    items.extend(backpack_items(&state.ecs, *state.ecs.fetch::<Entity>()));
+    let title = match state.ecs.read_storage::<Burden>().get(*state.ecs.fetch::<Entity>()) {
+        Some(burden) => format!("Inventory ({}/{} lb)", burden.carried, burden.capacity),
+        None => "Inventory".to_string(),
+    };
+    draw_inventory_menu(ctx, &title, &items)
+}
+
+pub fn draw_character_sheet(ecs: &World, ctx: &mut Rltk) -> ItemMenuResult {
+    let white = RGB::named(rltk::WHITE);
+    let black = RGB::named(rltk::BLACK);
+    let yellow = RGB::named(rltk::YELLOW);
+
+    let player_entity = ecs.fetch::<Entity>();
+    let attributes = ecs.read_storage::<Attributes>();
+    let combat_stats = ecs.read_storage::<CombatStats>();
+    let derived_stats = ecs.read_storage::<DerivedStats>();
+
+    let y = 10;
+    let x = 10;
+
+    ctx.draw_box(x, y, 31, 18, white, black);
+    ctx.print_color(x + 5, y, yellow, black, "Character");
+    ctx.print_color(x + 5, y + 18, yellow, black, "ESCAPE to close");
+
+    if let Some(attributes) = attributes.get(*player_entity) {
+        let rows = [
+            ("Might", &attributes.might),
+            ("Fitness", &attributes.fitness),
+            ("Quickness", &attributes.quickness),
+            ("Intelligence", &attributes.intelligence),
+        ];
+        for (i, (label, attribute)) in rows.iter().enumerate() {
+            ctx.print(x + 2, y + 2 + i as i32, label);
+            ctx.print(
+                x + 16,
+                y + 2 + i as i32,
+                &format!("{:>3} ({:+})", attribute.total(), attribute.bonus),
+            );
+        }
+    }
+
+    if let Some(stats) = combat_stats.get(*player_entity) {
+        ctx.print(x + 2, y + 7, &format!("Hit Points     {} / {}", stats.hp, stats.max_hp));
+    }
+
+    if let Some(derived) = derived_stats.get(*player_entity) {
+        ctx.print(x + 2, y + 8, &format!("To Hit         {:+}", derived.to_hit));
+        ctx.print(x + 2, y + 9, &format!("Carry Capacity {}", derived.carry_capacity));
+        ctx.print(x + 2, y + 10, &format!("Initiative     {:+}", derived.initiative));
+        ctx.print(x + 2, y + 11, &format!("Stealth        {:+}", derived.stealth));
+        ctx.print(
+            x + 2,
+            y + 12,
+            &format!("Regeneration   1 in {}", derived.regen_interval),
+        );
+    }
+
+    let statistics = ecs.fetch::<Statistics>();
+    ctx.print(x + 2, y + 14, &format!("Kills          {}", statistics.kills));
+    ctx.print(x + 2, y + 15, &format!("Damage Taken   {}", statistics.damage_taken));
+    ctx.print(x + 2, y + 16, &format!("Items Found    {}", statistics.items_picked_up));
+
+    match ctx.key {
+        Some(VirtualKeyCode::Escape) => ItemMenuResult::Cancel,
+        _ => ItemMenuResult::NoResponse,
+    }
 }
 
 pub fn ranged_targeting(state: &mut State, ctx: &mut Rltk, range: i32) -> (ItemMenuResult, Option<Point>) {
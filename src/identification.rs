@@ -0,0 +1,75 @@
+use crate::components::{Name, ObfuscatedName};
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+const POTION_ADJECTIVES: &[&str] = &[
+    "bubbling", "murky", "fizzy", "glowing", "smoky", "viscous", "cloudy", "oily",
+];
+const POTION_COLOURS: &[&str] = &[
+    "violet", "crimson", "amber", "teal", "silver", "black", "golden", "green",
+];
+const SCROLL_SYLLABLES: &[&str] = &[
+    "ka", "zu", "lor", "mex", "vy", "tha", "nir", "ob", "qua", "sel", "fen", "dru",
+];
+
+#[derive(Copy, Clone)]
+pub enum MagicItemKind {
+    Potion,
+    Scroll,
+}
+
+#[derive(Default)]
+pub struct Identification {
+    unidentified: HashMap<String, String>,
+    identified: HashSet<String>,
+}
+
+impl Identification {
+    pub fn obfuscate(&mut self, rng: &mut RandomNumberGenerator, real_name: &str, kind: MagicItemKind) -> String {
+        if let Some(name) = self.unidentified.get(real_name) {
+            return name.clone();
+        }
+
+        let name = loop {
+            let candidate = match kind {
+                MagicItemKind::Potion => format!(
+                    "{} {} potion",
+                    POTION_ADJECTIVES[rng.range(0, POTION_ADJECTIVES.len() as i32) as usize],
+                    POTION_COLOURS[rng.range(0, POTION_COLOURS.len() as i32) as usize]
+                ),
+                MagicItemKind::Scroll => {
+                    let label: String = (0..rng.roll_dice(1, 2) + 1)
+                        .map(|_| SCROLL_SYLLABLES[rng.range(0, SCROLL_SYLLABLES.len() as i32) as usize])
+                        .collect();
+                    format!("scroll labelled {}", label.to_uppercase())
+                }
+            };
+            if !self.unidentified.values().any(|name| *name == candidate) {
+                break candidate;
+            }
+        };
+        self.unidentified.insert(real_name.to_string(), name.clone());
+        name
+    }
+
+    pub fn identify(&mut self, real_name: &str) -> bool {
+        self.unidentified.contains_key(real_name) && self.identified.insert(real_name.to_string())
+    }
+
+    pub fn display_name(
+        &self,
+        entity: Entity,
+        names: &ReadStorage<Name>,
+        obfuscated_names: &ReadStorage<ObfuscatedName>,
+    ) -> String {
+        let name = match names.get(entity) {
+            Some(name) => &name.name,
+            None => return String::new(),
+        };
+        match obfuscated_names.get(entity) {
+            Some(obfuscated) if !self.identified.contains(name) => obfuscated.name.clone(),
+            _ => name.clone(),
+        }
+    }
+}
@@ -4,14 +4,22 @@ extern crate specs_derive;
 rltk::add_wasm_support!();
 
 mod components;
+mod dispatcher;
+mod effects;
+mod events;
+mod factions;
 mod game_log;
 mod gui;
+mod identification;
 mod map;
+mod noise;
 mod player;
+mod projectile;
 mod rect;
 mod save_load;
 mod spawner;
 mod state;
+mod statistics;
 mod systems;
 
 use crate::components::*;
@@ -27,7 +35,11 @@ const UI_HEIGHT: i32 = 10;
 
 fn main() {
     let context = Rltk::init_simple8x8(MAP_WIDTH as u32, (MAP_HEIGHT + UI_HEIGHT) as u32, "Rouge", "resources");
-    let mut state = State { ecs: World::new() };
+    let mut state = State {
+        ecs: World::new(),
+        systems: dispatcher::new_turn_dispatcher(),
+        event_subscribers: dispatcher::new_event_dispatcher(),
+    };
     state.ecs.register::<SimpleMarker<SerializeMe>>();
     state.ecs.register::<SerializationHelper>();
     state.ecs.register::<Position>();
@@ -35,25 +47,73 @@ fn main() {
     state.ecs.register::<Player>();
     state.ecs.register::<Viewshed>();
     state.ecs.register::<Monster>();
+    state.ecs.register::<ItemUser>();
+    state.ecs.register::<PackLeader>();
+    state.ecs.register::<PackFollower>();
+    state.ecs.register::<Ai>();
+    state.ecs.register::<Faction>();
     state.ecs.register::<Name>();
     state.ecs.register::<BlocksTile>();
     state.ecs.register::<CombatStats>();
+    state.ecs.register::<Attributes>();
+    state.ecs.register::<DerivedStats>();
+    state.ecs.register::<Burden>();
+    state.ecs.register::<HungerClock>();
+    state.ecs.register::<Resting>();
+    state.ecs.register::<Dead>();
+    state.ecs.register::<ProvidesFood>();
+    state.ecs.register::<Shatters>();
+    state.ecs.register::<ProvidesMagicMapping>();
+    state.ecs.register::<ProvidesTeleport>();
+    state.ecs.register::<ProvidesRemoveCurse>();
+    state.ecs.register::<Cursed>();
+    state.ecs.register::<Energy>();
+    state.ecs.register::<MyTurn>();
+    state.ecs.register::<MeleeAttack>();
     state.ecs.register::<WantsToMelee>();
     state.ecs.register::<SufferDamage>();
+    state.ecs.register::<Resistant>();
+    state.ecs.register::<Vulnerable>();
+    state.ecs.register::<Immune>();
     state.ecs.register::<Item>();
     state.ecs.register::<ProvidesHealing>();
     state.ecs.register::<Consumable>();
+    state.ecs.register::<Stackable>();
+    state.ecs.register::<Gold>();
+    state.ecs.register::<Purse>();
+    state.ecs.register::<Vendor>();
+    state.ecs.register::<Charges>();
+    state.ecs.register::<ProvidesRecharge>();
+    state.ecs.register::<ObfuscatedName>();
+    state.ecs.register::<ProvidesIdentification>();
     state.ecs.register::<InBackpack>();
     state.ecs.register::<WantsToPickupItem>();
     state.ecs.register::<WantsToUseItem>();
+    state.ecs.register::<WantsToThrowItem>();
     state.ecs.register::<WantsToDropItem>();
     state.ecs.register::<Ranged>();
     state.ecs.register::<InflictsDamage>();
     state.ecs.register::<Confusion>();
+    state.ecs.register::<Ability>();
+    state.ecs.register::<InflictsStatus>();
+    state.ecs.register::<StatusEffect>();
     state.ecs.register::<AreaOfEffect>();
+    state.ecs.register::<Equippable>();
+    state.ecs.register::<Equipped>();
+    state.ecs.register::<MeleePowerBonus>();
+    state.ecs.register::<DefenseBonus>();
+    state.ecs.register::<AttributeBonus>();
+    state.ecs.register::<WantsToRemoveItem>();
 
     state.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
     state.ecs.insert(rltk::RandomNumberGenerator::new());
+    state.ecs.insert(factions::FactionTable::default());
+    state.ecs.insert(noise::Noises::default());
+    state.ecs.insert(effects::EffectQueue::default());
+    state.ecs.insert(statistics::Statistics::default());
+    state.ecs.insert(statistics::Achievements::default());
+    state.ecs.insert(projectile::Projectiles::default());
+    state.ecs.insert(identification::Identification::default());
 
     let mut map = Map::new_map_with_rooms_and_corridors();
     let (player_x, player_y) = map.rooms[0].center();
@@ -62,6 +122,11 @@ fn main() {
         Map::populate_room(&mut state.ecs, room);
     }
 
+    if let Some(shop) = map.rooms.last().filter(|_| map.rooms.len() > 1) {
+        let (shop_x, shop_y) = shop.center();
+        spawner::vendor(&mut state.ecs, shop_x, shop_y);
+    }
+
     let player_entity = spawner::player(&mut state.ecs, player_x, player_y);
 
     state.ecs.insert(game_log::GameLog {
@@ -73,5 +138,9 @@ fn main() {
     state.ecs.insert(player_entity);
     state.ecs.insert(Point::new(player_x, player_y));
 
+    let mut game_events = events::GameEvents::default();
+    game_events.publish(events::GameEvent::LevelEntered { depth: 1 });
+    state.ecs.insert(game_events);
+
     rltk::main_loop(context, state)
 }
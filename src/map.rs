@@ -1,5 +1,5 @@
 use crate::rect::Rect;
-use crate::spawner::{random_item, random_monster};
+use crate::spawner::{form_packs, random_item, random_monster};
 use crate::{MAP_HEIGHT, MAP_WIDTH};
 use rltk::{Algorithm2D, BaseMap, Console, Point, RandomNumberGenerator, Rltk, RGB};
 use serde::{Deserialize, Serialize};
@@ -148,11 +148,13 @@ impl Map {
             }
         }
 
+        let mut monsters = Vec::new();
         for idx in monster_spawn_points.iter() {
             let x = idx % MAP_WIDTH;
             let y = idx / MAP_WIDTH;
-            random_monster(ecs, x, y);
+            monsters.push(random_monster(ecs, x, y));
         }
+        form_packs(ecs, &monsters);
         for idx in item_spawn_points.iter() {
             let x = idx % MAP_WIDTH;
             let y = idx / MAP_WIDTH;
@@ -0,0 +1,17 @@
+use rltk::Point;
+
+pub struct Noise {
+    pub origin: Point,
+    pub radius: i32,
+}
+
+#[derive(Default)]
+pub struct Noises {
+    pub sounds: Vec<Noise>,
+}
+
+impl Noises {
+    pub fn emit(&mut self, origin: Point, radius: i32) {
+        self.sounds.push(Noise { origin, radius });
+    }
+}
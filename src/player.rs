@@ -1,70 +1,220 @@
-use crate::components::{CombatStats, Item, Player, Position, Viewshed, WantsToMelee, WantsToPickupItem};
+use crate::components::{
+    Ai, AiState, CombatStats, DerivedStats, Energy, Faction, Item, MyTurn, Player, Position, Resting, StatusEffect,
+    StatusEffectType, Vendor, Viewshed, WantsToMelee, WantsToPickupItem,
+};
+use crate::factions::{self, FactionTable, Reaction};
+use crate::game_log::GameLog;
 use crate::gui;
 use crate::map::Map;
+use crate::noise::Noises;
 use crate::state::{RunState, State};
-use rltk::{console, Point, Rltk, VirtualKeyCode};
+use rltk::{console, Point, RandomNumberGenerator, Rltk, VirtualKeyCode};
 use specs::prelude::*;
 use std::cmp::{max, min};
 
-fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
-    let mut positions = ecs.write_storage::<Position>();
-    let mut players = ecs.write_storage::<Player>();
-    let mut viewsheds = ecs.write_storage::<Viewshed>();
-    let mut wants_to_melee = ecs.write_storage::<WantsToMelee>();
-    let entities = ecs.entities();
-    let combat_stats = ecs.read_storage::<CombatStats>();
-    let map = ecs.fetch::<Map>();
+const FOOTSTEP_NOISE: i32 = 4;
+
+pub fn spend_energy(ecs: &World, cost: i32) {
+    let player_entity = ecs.fetch::<Entity>();
+    let mut energies = ecs.write_storage::<Energy>();
+    let mut my_turns = ecs.write_storage::<MyTurn>();
+
+    my_turns.remove(*player_entity);
+    if let Some(energy) = energies.get_mut(*player_entity) {
+        energy.energy -= cost;
+    }
+}
+
+fn try_move_player(mut delta_x: i32, mut delta_y: i32, ecs: &mut World) -> RunState {
+    {
+        let player_entity = ecs.fetch::<Entity>();
+        let status_effects = ecs.read_storage::<StatusEffect>();
+        if StatusEffect::is_affected(&status_effects, *player_entity, StatusEffectType::Confused) {
+            let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+            delta_x = rng.range(-1, 2);
+            delta_y = rng.range(-1, 2);
+        }
+    }
 
-    for (entity, _player, pos, viewshed) in (&entities, &mut players, &mut positions, &mut viewsheds).join() {
-        let destination_idx = map.xy_to_idx(pos.x + delta_x, pos.y + delta_y);
-
-        for potential_target in map.tile_content[destination_idx].iter() {
-            let target = combat_stats.get(*potential_target);
-            match target {
-                None => {}
-                Some(_t) => {
-                    console::log("You attack monster".to_string());
-                    wants_to_melee
-                        .insert(
-                            entity,
-                            WantsToMelee {
-                                target: *potential_target,
-                            },
-                        )
-                        .expect("Add target failed");
+    if let Some(vendor) = vendor_at(ecs, delta_x, delta_y) {
+        return RunState::ShowVendor {
+            vendor,
+            mode: gui::VendorMode::Buy,
+        };
+    }
+
+    let cost = {
+        let mut positions = ecs.write_storage::<Position>();
+        let mut players = ecs.write_storage::<Player>();
+        let mut viewsheds = ecs.write_storage::<Viewshed>();
+        let mut wants_to_melee = ecs.write_storage::<WantsToMelee>();
+        let entities = ecs.entities();
+        let combat_stats = ecs.read_storage::<CombatStats>();
+        let derived_stats = ecs.read_storage::<DerivedStats>();
+        let map = ecs.fetch::<Map>();
+        let mut noises = ecs.write_resource::<Noises>();
+        let mut cost = Energy::MOVE_COST;
+
+        for (entity, _player, pos, viewshed) in (&entities, &mut players, &mut positions, &mut viewsheds).join() {
+            let destination_idx = map.xy_to_idx(pos.x + delta_x, pos.y + delta_y);
+
+            for potential_target in map.tile_content[destination_idx].iter() {
+                let target = combat_stats.get(*potential_target);
+                match target {
+                    None => {}
+                    Some(_t) => {
+                        console::log("You attack monster".to_string());
+                        wants_to_melee
+                            .insert(
+                                entity,
+                                WantsToMelee {
+                                    target: *potential_target,
+                                },
+                            )
+                            .expect("Add target failed");
+                        cost = Energy::ATTACK_COST;
+                    }
                 }
             }
+            if !map.blocked[destination_idx] {
+                pos.x = min(79, max(0, pos.x + delta_x));
+                pos.y = min(49, max(0, pos.y + delta_y));
+
+                viewshed.dirty = true;
+
+                let mut player_position = ecs.write_resource::<Point>();
+                player_position.x = pos.x;
+                player_position.y = pos.y;
+
+                let stealth = derived_stats.get(entity).map_or(0, |derived| derived.stealth);
+                noises.emit(*player_position, max(1, FOOTSTEP_NOISE - stealth));
+            }
         }
-        if !map.blocked[destination_idx] {
-            pos.x = min(79, max(0, pos.x + delta_x));
-            pos.y = min(49, max(0, pos.y + delta_y));
+        cost
+    };
+    spend_energy(ecs, cost);
+    RunState::PlayerTurn
+}
 
-            viewshed.dirty = true;
+fn vendor_at(ecs: &World, delta_x: i32, delta_y: i32) -> Option<Entity> {
+    let player_pos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let vendors = ecs.read_storage::<Vendor>();
+    let destination_idx = map.xy_to_idx(player_pos.x + delta_x, player_pos.y + delta_y);
+
+    map.tile_content[destination_idx]
+        .iter()
+        .find(|entity| vendors.get(**entity).is_some())
+        .copied()
+}
+
+fn hostile_in_view(ecs: &World) -> bool {
+    let player_entity = ecs.fetch::<Entity>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let factions = ecs.read_storage::<Faction>();
+    let ais = ecs.read_storage::<Ai>();
+    let faction_table = ecs.fetch::<FactionTable>();
+    let map = ecs.fetch::<Map>();
+
+    let hostile = |entity: &Entity| {
+        let awake = !matches!(ais.get(*entity), Some(ai) if ai.state == AiState::Sleep);
+        let attacks = matches!(
+            factions.get(*entity),
+            Some(faction) if faction_table.reaction(&faction.name, factions::PLAYER) == Reaction::Attack
+        );
+        awake && attacks
+    };
+    match viewsheds.get(*player_entity) {
+        None => false,
+        Some(viewshed) => viewshed
+            .visible_tiles
+            .iter()
+            .any(|tile| map.tile_content[map.point_to_idx(*tile)].iter().any(hostile)),
+    }
+}
 
-            let mut player_position = ecs.write_resource::<Point>();
-            player_position.x = pos.x;
-            player_position.y = pos.y;
+fn wait_turn(ecs: &mut World) {
+    spend_energy(ecs, Energy::ACTION_COST);
+}
+
+fn start_resting(ecs: &mut World) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let healed =
+        matches!(ecs.read_storage::<CombatStats>().get(player_entity), Some(stats) if stats.hp >= stats.max_hp);
+    let message = if healed {
+        "You are already at full health."
+    } else if hostile_in_view(ecs) {
+        "You cannot rest with enemies nearby."
+    } else {
+        ecs.write_storage::<Resting>()
+            .insert(player_entity, Resting {})
+            .expect("Unable to insert resting");
+        "You rest."
+    };
+    ecs.write_resource::<GameLog>().entries.insert(0, message.to_string());
+    RunState::AwaitingInput
+}
+
+fn continue_resting(ecs: &mut World, interrupted: bool) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let healed =
+        matches!(ecs.read_storage::<CombatStats>().get(player_entity), Some(stats) if stats.hp >= stats.max_hp);
+    let reason = if interrupted {
+        Some("You stop resting.")
+    } else if hostile_in_view(ecs) {
+        Some("You are disturbed!")
+    } else if healed {
+        Some("You feel rested.")
+    } else {
+        None
+    };
+
+    match reason {
+        Some(reason) => {
+            ecs.write_storage::<Resting>().remove(player_entity);
+            ecs.write_resource::<GameLog>().entries.insert(0, reason.to_string());
+            RunState::AwaitingInput
+        }
+        None => {
+            wait_turn(ecs);
+            RunState::PlayerTurn
         }
     }
 }
 
 pub fn player_input(state: &mut State, ctx: &mut Rltk) -> RunState {
+    let resting = state
+        .ecs
+        .read_storage::<Resting>()
+        .get(*state.ecs.fetch::<Entity>())
+        .is_some();
+    if resting {
+        return continue_resting(&mut state.ecs, ctx.key.is_some());
+    }
+
     match ctx.key {
         None => {
             return RunState::AwaitingInput;
         }
         Some(key) => match key {
-            VirtualKeyCode::Numpad1 => try_move_player(-1, 1, &mut state.ecs),
-            VirtualKeyCode::Numpad2 | VirtualKeyCode::Down => try_move_player(0, 1, &mut state.ecs),
-            VirtualKeyCode::Numpad3 => try_move_player(1, 1, &mut state.ecs),
-            VirtualKeyCode::Numpad4 | VirtualKeyCode::Left => try_move_player(-1, 0, &mut state.ecs),
-            VirtualKeyCode::Numpad6 | VirtualKeyCode::Right => try_move_player(1, 0, &mut state.ecs),
-            VirtualKeyCode::Numpad7 => try_move_player(-1, -1, &mut state.ecs),
-            VirtualKeyCode::Numpad8 | VirtualKeyCode::Up => try_move_player(0, -1, &mut state.ecs),
-            VirtualKeyCode::Numpad9 => try_move_player(1, -1, &mut state.ecs),
-            VirtualKeyCode::G => get_item(&mut state.ecs),
+            VirtualKeyCode::Numpad1 => return try_move_player(-1, 1, &mut state.ecs),
+            VirtualKeyCode::Numpad2 | VirtualKeyCode::Down => return try_move_player(0, 1, &mut state.ecs),
+            VirtualKeyCode::Numpad3 => return try_move_player(1, 1, &mut state.ecs),
+            VirtualKeyCode::Numpad4 | VirtualKeyCode::Left => return try_move_player(-1, 0, &mut state.ecs),
+            VirtualKeyCode::Numpad6 | VirtualKeyCode::Right => return try_move_player(1, 0, &mut state.ecs),
+            VirtualKeyCode::Numpad7 => return try_move_player(-1, -1, &mut state.ecs),
+            VirtualKeyCode::Numpad8 | VirtualKeyCode::Up => return try_move_player(0, -1, &mut state.ecs),
+            VirtualKeyCode::Numpad9 => return try_move_player(1, -1, &mut state.ecs),
+            VirtualKeyCode::Numpad5 | VirtualKeyCode::Period => wait_turn(&mut state.ecs),
+            VirtualKeyCode::R => return start_resting(&mut state.ecs),
+            VirtualKeyCode::G => {
+                get_item(&mut state.ecs);
+                spend_energy(&state.ecs, Energy::PICKUP_COST);
+            }
             VirtualKeyCode::I => return RunState::ShowInventory,
             VirtualKeyCode::D => return RunState::ShowDropItem,
+            VirtualKeyCode::T => return RunState::ShowThrowItem,
+            VirtualKeyCode::C => return RunState::ShowCharacterSheet,
             VirtualKeyCode::Escape => {
                 return RunState::MainMenu {
                     menu_selection: gui::MainMenuSelection::LoadGame,
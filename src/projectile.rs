@@ -0,0 +1,24 @@
+use rltk::{Point, RGB};
+
+pub struct Projectile {
+    pub path: Vec<Point>,
+    pub glyph: u8,
+    pub fg: RGB,
+    pub elapsed_ms: f32,
+}
+
+#[derive(Default)]
+pub struct Projectiles {
+    pub flights: Vec<Projectile>,
+}
+
+impl Projectiles {
+    pub fn launch(&mut self, path: Vec<Point>, glyph: u8, fg: RGB) {
+        self.flights.push(Projectile {
+            path,
+            glyph,
+            fg,
+            elapsed_ms: 0.0,
+        });
+    }
+}
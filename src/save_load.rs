@@ -44,22 +44,63 @@ pub fn save_game(ecs: &mut World) {
             Player,
             Viewshed,
             Monster,
+            ItemUser,
+            PackLeader,
+            PackFollower,
+            Ai,
+            Faction,
             Name,
             BlocksTile,
             CombatStats,
+            Attributes,
+            DerivedStats,
+            Burden,
+            HungerClock,
+            Resting,
+            Dead,
+            ProvidesFood,
+            Shatters,
+            ProvidesMagicMapping,
+            ProvidesTeleport,
+            ProvidesRemoveCurse,
+            Cursed,
+            Energy,
+            MyTurn,
             SufferDamage,
+            Resistant,
+            Vulnerable,
+            Immune,
+            MeleeAttack,
             WantsToMelee,
             Item,
             Consumable,
+            Stackable,
+            Gold,
+            Purse,
+            Vendor,
+            Charges,
+            ProvidesRecharge,
+            ObfuscatedName,
+            ProvidesIdentification,
             Ranged,
             InflictsDamage,
             AreaOfEffect,
             Confusion,
+            Ability,
+            InflictsStatus,
+            StatusEffect,
             ProvidesHealing,
             InBackpack,
             WantsToPickupItem,
             WantsToUseItem,
+            WantsToThrowItem,
             WantsToDropItem,
+            Equippable,
+            Equipped,
+            MeleePowerBonus,
+            DefenseBonus,
+            AttributeBonus,
+            WantsToRemoveItem,
             SerializationHelper
         );
     }
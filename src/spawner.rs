@@ -1,11 +1,32 @@
 use crate::components::{
-    AreaOfEffect, BlocksTile, CombatStats, Confusion, Consumable, InflictsDamage, Item, Monster, Name, Player,
-    Position, ProvidesHealing, Ranged, Renderable, SerializeMe, Viewshed,
+    Ability, Ai, AiState, AreaOfEffect, AttributeBonus, Attributes, BlocksTile, Charges, CombatStats, Confusion,
+    Consumable, Cursed, DamageType, DefenseBonus, Energy, EquipmentSlot, Equippable, Faction, Gold, HungerClock,
+    Immune, InBackpack, InflictsDamage, InflictsStatus, Item, ItemUser, MeleeAttack, MeleePowerBonus, Monster, Name,
+    ObfuscatedName, PackFollower, PackLeader, Player, Position, ProvidesFood, ProvidesHealing, ProvidesIdentification,
+    ProvidesMagicMapping, ProvidesRecharge, ProvidesRemoveCurse, ProvidesTeleport, Purse, Ranged, Renderable,
+    Resistant, SerializeMe, Shatters, Stackable, StatusEffectType, Vendor, Viewshed, Vulnerable,
 };
+use crate::factions;
+use crate::identification::{Identification, MagicItemKind};
 use rltk::{RandomNumberGenerator, RGB};
 use specs::prelude::*;
 use specs::saveload::{MarkedBuilder, SimpleMarker};
 
+const CURSE_CHANCE: i32 = 5;
+
+macro_rules! copy_components {
+    ($ecs:expr, $from:expr, $to:expr, $( $type:ty),*) => {
+        $(
+        let component = $ecs.read_storage::<$type>().get($from).cloned();
+        if let Some(component) = component {
+            $ecs.write_storage::<$type>()
+                .insert($to, component)
+                .expect("Unable to copy component");
+        }
+        )*
+    };
+}
+
 pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
     ecs.create_entity()
         .with(Position {
@@ -33,30 +54,214 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
             defense: 2,
             power: 5,
         })
+        .with(Attributes::new(12, 15, 12, 12))
+        .with(Energy::new(10))
+        .with(MeleeAttack::new("1d4", DamageType::Bludgeoning))
+        .with(Faction {
+            name: factions::PLAYER.to_string(),
+        })
+        .with(Purse { gold: 0 })
+        .with(HungerClock::new())
         .marked::<SimpleMarker<SerializeMe>>()
         .build()
 }
 
-pub fn random_monster(ecs: &mut World, x: i32, y: i32) {
+pub fn vendor(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let vendor = ecs
+        .create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('@'),
+            fg: RGB::named(rltk::GOLD),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 1,
+        })
+        .with(Name {
+            name: "Shopkeeper".to_string(),
+        })
+        .with(BlocksTile {})
+        .with(Vendor {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+
+    let stock_size = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        rng.roll_dice(2, 3) + 2
+    };
+    for _ in 0..stock_size {
+        let item = random_item(ecs, x, y);
+        if ecs.read_storage::<Gold>().get(item).is_some() {
+            ecs.delete_entity(item)
+                .expect("Unable to delete gold from vendor stock");
+            continue;
+        }
+        ecs.write_storage::<Position>().remove(item);
+        ecs.write_storage::<InBackpack>()
+            .insert(item, InBackpack { owner: vendor })
+            .expect("Unable to stock vendor");
+    }
+
+    vendor
+}
+
+pub fn random_monster(ecs: &mut World, x: i32, y: i32) -> Entity {
     let roll = {
         let mut rng = ecs.write_resource::<RandomNumberGenerator>();
-        rng.roll_dice(1, 2)
+        rng.roll_dice(1, 10)
     };
     match roll {
-        1 => orc(ecs, x, y),
-        _ => goblin(ecs, x, y),
-    };
+        1 | 2 => orc(ecs, x, y),
+        3 | 4 => goblin(ecs, x, y),
+        5 => bat(ecs, x, y),
+        6 => peasant(ecs, x, y),
+        7 => goblin_archer(ecs, x, y),
+        8 => orc_shaman(ecs, x, y),
+        9 => fire_elemental(ecs, x, y),
+        _ => ice_elemental(ecs, x, y),
+    }
 }
 
-fn orc(ecs: &mut World, x: i32, y: i32) {
-    monster(ecs, x, y, rltk::to_cp437('o'), "Orc")
+pub fn form_packs(ecs: &mut World, members: &[Entity]) {
+    let factions = ecs.read_storage::<Faction>();
+    let mut leaders = ecs.write_storage::<PackLeader>();
+    let mut followers = ecs.write_storage::<PackFollower>();
+    let mut packs: Vec<(String, Entity)> = Vec::new();
+
+    for member in members.iter() {
+        let faction = match factions.get(*member) {
+            Some(faction) => &faction.name,
+            None => continue,
+        };
+        match packs.iter().find(|(name, _)| name == faction) {
+            Some((_, leader)) => {
+                leaders
+                    .insert(*leader, PackLeader {})
+                    .expect("Unable to insert pack leader");
+                followers
+                    .insert(*member, PackFollower { leader: *leader })
+                    .expect("Unable to insert pack follower");
+            }
+            None => packs.push((faction.clone(), *member)),
+        }
+    }
 }
 
-fn goblin(ecs: &mut World, x: i32, y: i32) {
-    monster(ecs, x, y, rltk::to_cp437('g'), "Goblin")
+fn orc(ecs: &mut World, x: i32, y: i32) -> Entity {
+    monster(ecs, x, y, rltk::to_cp437('o'), "Orc", factions::ORCS)
+        .with(ItemUser {})
+        .with(MeleeAttack::new("1d6", DamageType::Slashing))
+        .with(Energy::new(10))
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
 }
 
-fn monster(ecs: &mut World, x: i32, y: i32, glyph: u8, name: &str) {
+fn goblin(ecs: &mut World, x: i32, y: i32) -> Entity {
+    monster(ecs, x, y, rltk::to_cp437('g'), "Goblin", factions::GOBLINS)
+        .with(ItemUser {})
+        .with(MeleeAttack::new("1d4+1", DamageType::Piercing))
+        .with(Energy::new(10))
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn goblin_archer(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let archer = monster(ecs, x, y, rltk::to_cp437('g'), "Goblin Archer", factions::GOBLINS)
+        .with(MeleeAttack::new("1d3", DamageType::Piercing))
+        .with(Energy::new(10))
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+    ability(ecs, archer, "Arrow", 6, 1)
+        .with(InflictsDamage {
+            damage: 4,
+            damage_type: DamageType::Piercing,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+    archer
+}
+
+fn orc_shaman(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let shaman = monster(ecs, x, y, rltk::to_cp437('o'), "Orc Shaman", factions::ORCS)
+        .with(ItemUser {})
+        .with(MeleeAttack::new("1d4", DamageType::Bludgeoning))
+        .with(Energy::new(10))
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+    ability(ecs, shaman, "Fire Bolt", 6, 3)
+        .with(InflictsDamage {
+            damage: 6,
+            damage_type: DamageType::Fire,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+    ability(ecs, shaman, "Confusing Hex", 5, 8)
+        .with(Confusion { turns: 3 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+    shaman
+}
+
+fn ability<'a>(ecs: &'a mut World, owner: Entity, name: &str, range: i32, cooldown: i32) -> EntityBuilder<'a> {
+    ecs.create_entity()
+        .with(Name { name: name.to_string() })
+        .with(Ability {
+            owner,
+            cooldown,
+            turns_until_ready: 0,
+        })
+        .with(Ranged { range })
+}
+
+fn bat(ecs: &mut World, x: i32, y: i32) -> Entity {
+    monster(ecs, x, y, rltk::to_cp437('b'), "Bat", factions::ANIMALS)
+        .with(MeleeAttack::new("1d2", DamageType::Piercing))
+        .with(Energy::new(20))
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn fire_elemental(ecs: &mut World, x: i32, y: i32) -> Entity {
+    monster(ecs, x, y, rltk::to_cp437('E'), "Fire Elemental", factions::ELEMENTALS)
+        .with(MeleeAttack::new("1d6", DamageType::Fire))
+        .with(Energy::new(8))
+        .with(Immune {
+            damage_types: vec![DamageType::Fire],
+        })
+        .with(Vulnerable {
+            damage_types: vec![DamageType::Cold],
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn ice_elemental(ecs: &mut World, x: i32, y: i32) -> Entity {
+    monster(ecs, x, y, rltk::to_cp437('E'), "Ice Elemental", factions::ELEMENTALS)
+        .with(MeleeAttack::new("1d6", DamageType::Cold))
+        .with(Energy::new(8))
+        .with(Resistant {
+            damage_types: vec![DamageType::Cold, DamageType::Piercing],
+        })
+        .with(Vulnerable {
+            damage_types: vec![DamageType::Fire],
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn peasant(ecs: &mut World, x: i32, y: i32) -> Entity {
+    monster(ecs, x, y, rltk::to_cp437('p'), "Peasant", factions::TOWNSFOLK)
+        .with(MeleeAttack::new("1d2", DamageType::Bludgeoning))
+        .with(Energy::new(10))
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn monster<'a>(ecs: &'a mut World, x: i32, y: i32, glyph: u8, name: &str, faction: &str) -> EntityBuilder<'a> {
+    let mut ai = Ai::default();
+    if ecs.write_resource::<RandomNumberGenerator>().roll_dice(1, 3) == 1 {
+        ai.state = AiState::Sleep;
+    }
+
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -71,6 +276,10 @@ fn monster(ecs: &mut World, x: i32, y: i32, glyph: u8, name: &str) {
             dirty: true,
         })
         .with(Monster {})
+        .with(ai)
+        .with(Faction {
+            name: faction.to_string(),
+        })
         .with(Name { name: name.to_string() })
         .with(BlocksTile {})
         .with(CombatStats {
@@ -79,11 +288,56 @@ fn monster(ecs: &mut World, x: i32, y: i32, glyph: u8, name: &str) {
             defense: 1,
             power: 4,
         })
+        .with(Attributes::new(10, 8, 10, 6))
+}
+
+fn gold(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let amount = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        rng.roll_dice(2, 10)
+    };
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('$'),
+            fg: RGB::named(rltk::GOLD),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Gold".to_string(),
+        })
+        .with(Item {
+            weight: 0,
+            value: amount,
+        })
+        .with(Gold { amount })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-pub fn health_potion(ecs: &mut World, x: i32, y: i32) {
+fn ration(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('%'),
+            fg: RGB::named(rltk::GREEN),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Ration".to_string(),
+        })
+        .with(Item { weight: 2, value: 10 })
+        .with(Consumable {})
+        .with(Stackable { quantity: 1 })
+        .with(ProvidesFood {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+pub fn health_potion(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = obfuscated_name(ecs, "Health Potion", MagicItemKind::Potion);
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -95,14 +349,18 @@ pub fn health_potion(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Health Potion".to_string(),
         })
-        .with(Item {})
+        .with(obfuscated_name)
+        .with(Item { weight: 1, value: 50 })
         .with(Consumable {})
+        .with(Stackable { quantity: 1 })
         .with(ProvidesHealing { heal_amount: 8 })
+        .with(Shatters {})
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) {
+fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = obfuscated_name(ecs, "Magic Missile Scroll", MagicItemKind::Scroll);
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -114,15 +372,21 @@ fn magic_missile_scroll(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Magic Missile Scroll".to_string(),
         })
-        .with(Item {})
+        .with(obfuscated_name)
+        .with(Item { weight: 1, value: 60 })
         .with(Consumable {})
+        .with(Stackable { quantity: 1 })
         .with(Ranged { range: 6 })
-        .with(InflictsDamage { damage: 8 })
+        .with(InflictsDamage {
+            damage: 8,
+            damage_type: DamageType::Piercing,
+        })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
 
-fn fireball_scroll(ecs: &mut World, x: i32, y: i32) {
+fn fireball_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = obfuscated_name(ecs, "Fireball Scroll", MagicItemKind::Scroll);
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -134,15 +398,21 @@ fn fireball_scroll(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Fireball Scroll".to_string(),
         })
-        .with(Item {})
+        .with(obfuscated_name)
+        .with(Item { weight: 1, value: 120 })
         .with(Consumable {})
+        .with(Stackable { quantity: 1 })
         .with(Ranged { range: 6 })
-        .with(InflictsDamage { damage: 20 })
+        .with(InflictsDamage {
+            damage: 20,
+            damage_type: DamageType::Fire,
+        })
         .with(AreaOfEffect { radius: 3 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
 }
-fn confusion_scroll(ecs: &mut World, x: i32, y: i32) {
+fn confusion_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = obfuscated_name(ecs, "Confusion Scroll", MagicItemKind::Scroll);
     ecs.create_entity()
         .with(Position { x, y })
         .with(Renderable {
@@ -154,23 +424,484 @@ fn confusion_scroll(ecs: &mut World, x: i32, y: i32) {
         .with(Name {
             name: "Confusion Scroll".to_string(),
         })
-        .with(Item {})
+        .with(obfuscated_name)
+        .with(Item { weight: 1, value: 80 })
         .with(Consumable {})
+        .with(Stackable { quantity: 1 })
         .with(Ranged { range: 6 })
         .with(Confusion { turns: 4 })
         .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+        .build()
+}
+
+fn identify_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = obfuscated_name(ecs, "Identify Scroll", MagicItemKind::Scroll);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(')'),
+            fg: RGB::named(rltk::WHITE),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Identify Scroll".to_string(),
+        })
+        .with(obfuscated_name)
+        .with(Item { weight: 1, value: 50 })
+        .with(Consumable {})
+        .with(Stackable { quantity: 1 })
+        .with(ProvidesIdentification {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
 }
 
-pub fn random_item(ecs: &mut World, x: i32, y: i32) {
+fn magic_mapping_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = obfuscated_name(ecs, "Magic Mapping Scroll", MagicItemKind::Scroll);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(')'),
+            fg: RGB::named(rltk::CYAN),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Magic Mapping Scroll".to_string(),
+        })
+        .with(obfuscated_name)
+        .with(Item { weight: 1, value: 75 })
+        .with(Consumable {})
+        .with(Stackable { quantity: 1 })
+        .with(ProvidesMagicMapping {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn teleport_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = obfuscated_name(ecs, "Teleport Scroll", MagicItemKind::Scroll);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(')'),
+            fg: RGB::named(rltk::PURPLE),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Teleport Scroll".to_string(),
+        })
+        .with(obfuscated_name)
+        .with(Item { weight: 1, value: 60 })
+        .with(Consumable {})
+        .with(Stackable { quantity: 1 })
+        .with(ProvidesTeleport {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+pub fn split_stack(ecs: &mut World, stack: Entity) -> Entity {
+    let item = ecs.create_entity().marked::<SimpleMarker<SerializeMe>>().build();
+    copy_components!(
+        ecs,
+        stack,
+        item,
+        Renderable,
+        Name,
+        ObfuscatedName,
+        Item,
+        Consumable,
+        ProvidesHealing,
+        Ranged,
+        InflictsDamage,
+        AreaOfEffect,
+        Confusion,
+        InflictsStatus,
+        ProvidesIdentification,
+        ProvidesRecharge,
+        ProvidesFood,
+        ProvidesMagicMapping,
+        ProvidesTeleport,
+        ProvidesRemoveCurse,
+        Shatters,
+        InBackpack
+    );
+
+    let mut stackables = ecs.write_storage::<Stackable>();
+    if let Some(stackable) = stackables.get_mut(stack) {
+        stackable.quantity -= 1;
+    }
+    stackables
+        .insert(item, Stackable { quantity: 1 })
+        .expect("Unable to insert stack");
+    item
+}
+
+fn recharging_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = obfuscated_name(ecs, "Recharging Scroll", MagicItemKind::Scroll);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(')'),
+            fg: RGB::named(rltk::YELLOW),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Recharging Scroll".to_string(),
+        })
+        .with(obfuscated_name)
+        .with(Item { weight: 1, value: 150 })
+        .with(Consumable {})
+        .with(Stackable { quantity: 1 })
+        .with(ProvidesRecharge {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn wand<'a>(ecs: &'a mut World, x: i32, y: i32, name: &str, fg: RGB, max_charges: i32) -> EntityBuilder<'a> {
+    let current = ecs.write_resource::<RandomNumberGenerator>().range(1, max_charges + 1);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('/'),
+            fg,
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name { name: name.to_string() })
+        .with(Ranged { range: 6 })
+        .with(Charges {
+            current,
+            max: max_charges,
+        })
+}
+
+fn wand_of_magic_missile(ecs: &mut World, x: i32, y: i32) -> Entity {
+    wand(ecs, x, y, "Wand of Magic Missile", RGB::named(rltk::CYAN), 5)
+        .with(Item { weight: 2, value: 200 })
+        .with(InflictsDamage {
+            damage: 6,
+            damage_type: DamageType::Piercing,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn wand_of_slowness(ecs: &mut World, x: i32, y: i32) -> Entity {
+    wand(ecs, x, y, "Wand of Slowness", RGB::named(rltk::PINK), 4)
+        .with(Item { weight: 2, value: 150 })
+        .with(InflictsStatus {
+            effect_type: StatusEffectType::Slowed,
+            turns: 6,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn wand_of_frost(ecs: &mut World, x: i32, y: i32) -> Entity {
+    wand(ecs, x, y, "Wand of Frost", RGB::named(rltk::LIGHT_BLUE), 4)
+        .with(Item { weight: 2, value: 180 })
+        .with(InflictsDamage {
+            damage: 8,
+            damage_type: DamageType::Cold,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn staff_of_fire(ecs: &mut World, x: i32, y: i32) -> Entity {
+    wand(ecs, x, y, "Staff of Fire", RGB::named(rltk::ORANGE), 3)
+        .with(Item { weight: 6, value: 300 })
+        .with(InflictsDamage {
+            damage: 12,
+            damage_type: DamageType::Fire,
+        })
+        .with(AreaOfEffect { radius: 2 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn obfuscated_name(ecs: &mut World, real_name: &str, kind: MagicItemKind) -> ObfuscatedName {
+    let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+    let mut identification = ecs.write_resource::<Identification>();
+    ObfuscatedName {
+        name: identification.obfuscate(&mut rng, real_name, kind),
+    }
+}
+
+fn regeneration_potion(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = obfuscated_name(ecs, "Regeneration Potion", MagicItemKind::Potion);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(';'),
+            fg: RGB::named(rltk::GREEN),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 1,
+        })
+        .with(Name {
+            name: "Regeneration Potion".to_string(),
+        })
+        .with(obfuscated_name)
+        .with(Item { weight: 1, value: 80 })
+        .with(Consumable {})
+        .with(Stackable { quantity: 1 })
+        .with(InflictsStatus {
+            effect_type: StatusEffectType::Regenerating,
+            turns: 10,
+        })
+        .with(Shatters {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn status_scroll<'a>(
+    ecs: &'a mut World,
+    x: i32,
+    y: i32,
+    name: &str,
+    effect_type: StatusEffectType,
+    turns: i32,
+) -> EntityBuilder<'a> {
+    let obfuscated_name = obfuscated_name(ecs, name, MagicItemKind::Scroll);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(')'),
+            fg: RGB::named(rltk::PINK),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name { name: name.to_string() })
+        .with(obfuscated_name)
+        .with(Consumable {})
+        .with(Stackable { quantity: 1 })
+        .with(Ranged { range: 6 })
+        .with(InflictsStatus { effect_type, turns })
+}
+
+fn slowness_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    status_scroll(ecs, x, y, "Slowness Scroll", StatusEffectType::Slowed, 8)
+        .with(Item { weight: 1, value: 60 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn thunderclap_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    status_scroll(ecs, x, y, "Thunderclap Scroll", StatusEffectType::Stunned, 2)
+        .with(Item { weight: 1, value: 90 })
+        .with(AreaOfEffect { radius: 2 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn poison_gas_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    status_scroll(ecs, x, y, "Poison Gas Scroll", StatusEffectType::Poisoned, 5)
+        .with(Item { weight: 1, value: 90 })
+        .with(AreaOfEffect { radius: 2 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn dagger(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('/'),
+            fg: RGB::named(rltk::CYAN),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Dagger".to_string(),
+        })
+        .with(Item { weight: 2, value: 20 })
+        .with(Equippable {
+            slot: EquipmentSlot::Melee,
+        })
+        .with(MeleeAttack::new("1d4", DamageType::Piercing))
+        .with(MeleePowerBonus { power: 2 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn longsword(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('/'),
+            fg: RGB::named(rltk::YELLOW),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Longsword".to_string(),
+        })
+        .with(Item { weight: 6, value: 60 })
+        .with(Equippable {
+            slot: EquipmentSlot::Melee,
+        })
+        .with(MeleeAttack::new("1d8", DamageType::Slashing))
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn armour<'a>(
+    ecs: &'a mut World,
+    x: i32,
+    y: i32,
+    glyph: char,
+    name: &str,
+    slot: EquipmentSlot,
+    defense: i32,
+) -> EntityBuilder<'a> {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(glyph),
+            fg: RGB::named(rltk::CYAN),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name { name: name.to_string() })
+        .with(Equippable { slot })
+        .with(DefenseBonus { defense })
+}
+
+fn shield(ecs: &mut World, x: i32, y: i32) -> Entity {
+    armour(ecs, x, y, '(', "Shield", EquipmentSlot::Shield, 1)
+        .with(Item { weight: 10, value: 30 })
+        .with(AttributeBonus {
+            quickness: -1,
+            ..AttributeBonus::default()
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn helmet(ecs: &mut World, x: i32, y: i32) -> Entity {
+    armour(ecs, x, y, '[', "Helmet", EquipmentSlot::Head, 1)
+        .with(Item { weight: 5, value: 25 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn leather_armour(ecs: &mut World, x: i32, y: i32) -> Entity {
+    armour(ecs, x, y, '[', "Leather Armour", EquipmentSlot::Body, 2)
+        .with(Item { weight: 15, value: 40 })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn ring_of_power(ecs: &mut World, x: i32, y: i32) -> Entity {
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437('='),
+            fg: RGB::named(rltk::GOLD),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Ring of Power".to_string(),
+        })
+        .with(Item { weight: 0, value: 250 })
+        .with(Equippable {
+            slot: EquipmentSlot::Ring,
+        })
+        .with(MeleePowerBonus { power: 1 })
+        .with(AttributeBonus {
+            might: 2,
+            ..AttributeBonus::default()
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn remove_curse_scroll(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let obfuscated_name = obfuscated_name(ecs, "Remove Curse Scroll", MagicItemKind::Scroll);
+    ecs.create_entity()
+        .with(Position { x, y })
+        .with(Renderable {
+            glyph: rltk::to_cp437(')'),
+            fg: RGB::named(rltk::WHITE),
+            bg: RGB::named(rltk::BLACK),
+            render_order: 2,
+        })
+        .with(Name {
+            name: "Remove Curse Scroll".to_string(),
+        })
+        .with(obfuscated_name)
+        .with(Item { weight: 1, value: 60 })
+        .with(Consumable {})
+        .with(Stackable { quantity: 1 })
+        .with(ProvidesRemoveCurse {})
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build()
+}
+
+fn curse(ecs: &mut World, item: Entity) {
+    let power = ecs.read_storage::<MeleePowerBonus>().get(item).map(|bonus| bonus.power);
+    let defense = ecs.read_storage::<DefenseBonus>().get(item).map(|bonus| bonus.defense);
+    let (power_penalty, defense_penalty) = match (power, defense) {
+        (None, None) => (1, 0),
+        (power, defense) => (power.unwrap_or(0) * 2, defense.unwrap_or(0) * 2),
+    };
+    ecs.write_storage::<Cursed>()
+        .insert(
+            item,
+            Cursed {
+                revealed: false,
+                power_penalty,
+                defense_penalty,
+            },
+        )
+        .expect("Unable to curse item");
+}
+
+pub fn random_item(ecs: &mut World, x: i32, y: i32) -> Entity {
+    let item = roll_item(ecs, x, y);
+    let cursed = {
+        let mut rng = ecs.write_resource::<RandomNumberGenerator>();
+        ecs.read_storage::<Equippable>().get(item).is_some() && rng.roll_dice(1, CURSE_CHANCE) == 1
+    };
+    if cursed {
+        curse(ecs, item);
+    }
+    item
+}
+
+fn roll_item(ecs: &mut World, x: i32, y: i32) -> Entity {
     let roll = {
         let mut rng = ecs.write_resource::<RandomNumberGenerator>();
-        rng.roll_dice(1, 4)
+        rng.roll_dice(1, 28)
     };
     match roll {
-        1 => health_potion(ecs, x, y),
-        2 => fireball_scroll(ecs, x, y),
-        3 => confusion_scroll(ecs, x, y),
+        1..=3 => health_potion(ecs, x, y),
+        4 => fireball_scroll(ecs, x, y),
+        5 => confusion_scroll(ecs, x, y),
+        6 => dagger(ecs, x, y),
+        7 => longsword(ecs, x, y),
+        8 => shield(ecs, x, y),
+        9 => helmet(ecs, x, y),
+        10 => leather_armour(ecs, x, y),
+        11 => ring_of_power(ecs, x, y),
+        12 => regeneration_potion(ecs, x, y),
+        13 => slowness_scroll(ecs, x, y),
+        14 => thunderclap_scroll(ecs, x, y),
+        15 => poison_gas_scroll(ecs, x, y),
+        16 => identify_scroll(ecs, x, y),
+        18 => wand_of_magic_missile(ecs, x, y),
+        19 => wand_of_slowness(ecs, x, y),
+        20 => staff_of_fire(ecs, x, y),
+        21 => recharging_scroll(ecs, x, y),
+        22 => gold(ecs, x, y),
+        23 | 24 => ration(ecs, x, y),
+        25 => magic_mapping_scroll(ecs, x, y),
+        26 => teleport_scroll(ecs, x, y),
+        27 => remove_curse_scroll(ecs, x, y),
+        28 => wand_of_frost(ecs, x, y),
         _ => magic_missile_scroll(ecs, x, y),
-    };
+    }
 }
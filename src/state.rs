@@ -1,11 +1,14 @@
-use crate::components::{CombatStats, Name, Player, Position, Ranged, Renderable, WantsToDropItem, WantsToUseItem};
+use crate::components::{
+    Ability, Charges, CombatStats, Dead, Energy, Equippable, Equipped, InBackpack, Item, Name, ObfuscatedName,
+    PackFollower, PackLeader, Player, Position, Purse, Ranged, Renderable, Stackable, StatusEffect, WantsToDropItem,
+    WantsToRemoveItem, WantsToThrowItem, WantsToUseItem,
+};
+use crate::dispatcher::UnifiedDispatcher;
+use crate::events::{GameEvent, GameEvents};
 use crate::game_log::GameLog;
+use crate::identification::Identification;
 use crate::map::Map;
-use crate::systems::{
-    DamageSystem, ItemCollectionSystem, ItemDropSystem, ItemUseSystem, MapIndexingSystem, MeleeCombatSystem, MonsterAI,
-    VisibilitySystem,
-};
-use crate::{gui, player, save_load};
+use crate::{gui, player, save_load, spawner};
 use rltk::{Console, GameState, Rltk};
 use specs::prelude::*;
 
@@ -14,36 +17,36 @@ pub enum RunState {
     AwaitingInput,
     PreRun,
     PlayerTurn,
-    MonsterTurn,
+    Ticking,
     ShowInventory,
     ShowDropItem,
+    ShowThrowItem,
+    ShowThrowTargeting { item: Entity },
+    ShowCharacterSheet,
+    ShowVendor { vendor: Entity, mode: gui::VendorMode },
+    MagicMapReveal { row: i32 },
     ShowTargeting { range: i32, item: Entity },
     MainMenu { menu_selection: gui::MainMenuSelection },
 }
 
+const THROW_RANGE: i32 = 6;
+
 pub struct State {
     pub ecs: World,
+    pub systems: Box<dyn UnifiedDispatcher>,
+    pub event_subscribers: Box<dyn UnifiedDispatcher>,
 }
 
 impl State {
     fn run_systems(&mut self) {
-        let mut visibility_system = VisibilitySystem {};
-        visibility_system.run_now(&self.ecs);
-        let mut monster_ai = MonsterAI {};
-        monster_ai.run_now(&self.ecs);
-        let mut map_indexing_system = MapIndexingSystem {};
-        map_indexing_system.run_now(&self.ecs);
-        let mut melee_combat_system = MeleeCombatSystem {};
-        melee_combat_system.run_now(&self.ecs);
-        let mut damage_system = DamageSystem {};
-        damage_system.run_now(&self.ecs);
-        let mut item_collection_system = ItemCollectionSystem {};
-        item_collection_system.run_now(&self.ecs);
-        let mut item_use_system = ItemUseSystem {};
-        item_use_system.run_now(&self.ecs);
-        let mut item_drop_items = ItemDropSystem {};
-        item_drop_items.run_now(&self.ecs);
+        self.systems.run_now(&self.ecs);
         self.ecs.maintain();
+        remove_the_dead(&mut self.ecs);
+    }
+
+    fn dispatch_events(&mut self) {
+        self.event_subscribers.run_now(&self.ecs);
+        self.ecs.write_resource::<GameEvents>().events.clear();
     }
 }
 
@@ -68,6 +71,7 @@ impl GameState for State {
                         ctx.set(pos.x, pos.y, render.fg, render.bg, render.glyph)
                     }
                 }
+                gui::draw_projectiles(&self.ecs, ctx);
                 gui::draw_ui(&self.ecs, ctx);
             }
         }
@@ -75,16 +79,33 @@ impl GameState for State {
         let newrunstate = match runstate {
             RunState::PreRun => {
                 self.run_systems();
-                RunState::AwaitingInput
+                RunState::Ticking
             }
             RunState::AwaitingInput => player::player_input(self, ctx),
             RunState::PlayerTurn => {
                 self.run_systems();
-                RunState::MonsterTurn
+                match *self.ecs.fetch::<RunState>() {
+                    RunState::MagicMapReveal { .. } => RunState::MagicMapReveal { row: 0 },
+                    _ => RunState::Ticking,
+                }
             }
-            RunState::MonsterTurn => {
-                self.run_systems();
-                RunState::AwaitingInput
+            RunState::MagicMapReveal { row } => {
+                let mut map = self.ecs.fetch_mut::<Map>();
+                for x in 0..map.width {
+                    let idx = map.xy_to_idx(x, row);
+                    map.revealed_tiles[idx] = true;
+                }
+                if row >= map.height - 1 {
+                    RunState::Ticking
+                } else {
+                    RunState::MagicMapReveal { row: row + 1 }
+                }
+            }
+            RunState::Ticking => {
+                while *self.ecs.fetch::<RunState>() == RunState::Ticking {
+                    self.run_systems();
+                }
+                *self.ecs.fetch::<RunState>()
             }
             RunState::ShowInventory => {
                 let (result, entity) = gui::draw_show_inventory_item_menu(self, ctx);
@@ -95,7 +116,16 @@ impl GameState for State {
                         let item = entity.unwrap();
                         let is_ranged = self.ecs.read_storage::<Ranged>();
                         let is_item_ranged = is_ranged.get(item);
-                        if let Some(is_item_ranged) = is_item_ranged {
+                        if self.ecs.read_storage::<Equipped>().get(item).is_some() {
+                            let mut wants_to_remove_item = self.ecs.write_storage::<WantsToRemoveItem>();
+                            wants_to_remove_item
+                                .insert(*self.ecs.fetch::<Entity>(), WantsToRemoveItem { item })
+                                .expect("Unable to insert item to wants_to_remove_item");
+                            player::spend_energy(&self.ecs, Energy::EQUIP_COST);
+                            RunState::PlayerTurn
+                        } else if out_of_charges(&self.ecs, item) {
+                            RunState::AwaitingInput
+                        } else if let Some(is_item_ranged) = is_item_ranged {
                             RunState::ShowTargeting {
                                 range: is_item_ranged.range,
                                 item,
@@ -105,6 +135,12 @@ impl GameState for State {
                             wants_to_use_item
                                 .insert(*self.ecs.fetch::<Entity>(), WantsToUseItem { item, target: None })
                                 .expect("Unable to insert item to wants_to_use_item");
+                            let cost = if self.ecs.read_storage::<Equippable>().get(item).is_some() {
+                                Energy::EQUIP_COST
+                            } else {
+                                Energy::USE_ITEM_COST
+                            };
+                            player::spend_energy(&self.ecs, cost);
                             RunState::PlayerTurn
                         }
                     }
@@ -117,24 +153,90 @@ impl GameState for State {
                     gui::ItemMenuResult::NoResponse => RunState::ShowDropItem,
                     gui::ItemMenuResult::Selected => {
                         let item = entity.unwrap();
+                        let stacked = matches!(
+                            self.ecs.read_storage::<Stackable>().get(item),
+                            Some(stack) if stack.quantity > 1
+                        );
+                        let item = if stacked {
+                            spawner::split_stack(&mut self.ecs, item)
+                        } else {
+                            item
+                        };
                         let mut wants_to_drop_potion = self.ecs.write_storage::<WantsToDropItem>();
                         wants_to_drop_potion
                             .insert(*self.ecs.fetch::<Entity>(), WantsToDropItem { item })
                             .expect("Unable to insert potion to wants_to_drop_potion");
+                        player::spend_energy(&self.ecs, Energy::DROP_COST);
                         RunState::PlayerTurn
                     }
                 }
             }
+            RunState::ShowVendor { vendor, mode } => {
+                let (result, entity) = gui::draw_vendor_menu(self, ctx, vendor, mode);
+                match result {
+                    gui::VendorResult::Cancel => RunState::AwaitingInput,
+                    gui::VendorResult::NoResponse => RunState::ShowVendor { vendor, mode },
+                    gui::VendorResult::ToggleMode => RunState::ShowVendor {
+                        vendor,
+                        mode: match mode {
+                            gui::VendorMode::Buy => gui::VendorMode::Sell,
+                            gui::VendorMode::Sell => gui::VendorMode::Buy,
+                        },
+                    },
+                    gui::VendorResult::Selected => {
+                        trade(&mut self.ecs, vendor, entity.unwrap(), mode);
+                        RunState::ShowVendor { vendor, mode }
+                    }
+                }
+            }
+            RunState::ShowCharacterSheet => match gui::draw_character_sheet(&self.ecs, ctx) {
+                gui::ItemMenuResult::Cancel => RunState::AwaitingInput,
+                _ => RunState::ShowCharacterSheet,
+            },
+            RunState::ShowThrowItem => {
+                let (result, entity) = gui::draw_throw_item_menu(self, ctx);
+                match result {
+                    gui::ItemMenuResult::Cancel => RunState::AwaitingInput,
+                    gui::ItemMenuResult::NoResponse => RunState::ShowThrowItem,
+                    gui::ItemMenuResult::Selected => RunState::ShowThrowTargeting { item: entity.unwrap() },
+                }
+            }
+            RunState::ShowThrowTargeting { item } => {
+                let (result, target) = gui::ranged_targeting(self, ctx, THROW_RANGE);
+                match (result, target) {
+                    (gui::ItemMenuResult::Cancel, _) => RunState::AwaitingInput,
+                    (gui::ItemMenuResult::Selected, Some(target)) => {
+                        let stacked = matches!(
+                            self.ecs.read_storage::<Stackable>().get(item),
+                            Some(stack) if stack.quantity > 1
+                        );
+                        let item = if stacked {
+                            spawner::split_stack(&mut self.ecs, item)
+                        } else {
+                            item
+                        };
+                        let mut intent = self.ecs.write_storage::<WantsToThrowItem>();
+                        intent
+                            .insert(*self.ecs.fetch::<Entity>(), WantsToThrowItem { item, target })
+                            .expect("Unable to insert want to throw item");
+                        player::spend_energy(&self.ecs, Energy::THROW_COST);
+                        RunState::PlayerTurn
+                    }
+                    _ => RunState::ShowThrowTargeting { item },
+                }
+            }
             RunState::ShowTargeting { range, item } => {
                 let (result, target) = gui::ranged_targeting(self, ctx, range);
                 match result {
                     gui::ItemMenuResult::Cancel => RunState::AwaitingInput,
                     gui::ItemMenuResult::NoResponse => RunState::ShowTargeting { range, item },
+                    gui::ItemMenuResult::Selected if out_of_charges(&self.ecs, item) => RunState::AwaitingInput,
                     gui::ItemMenuResult::Selected => {
                         let mut intent = self.ecs.write_storage::<WantsToUseItem>();
                         intent
                             .insert(*self.ecs.fetch::<Entity>(), WantsToUseItem { item, target })
                             .expect("Unable to insert target to want_to_use_item");
+                        player::spend_energy(&self.ecs, Energy::USE_ITEM_COST);
                         RunState::PlayerTurn
                     }
                 }
@@ -163,8 +265,93 @@ impl GameState for State {
             let mut runwriter = self.ecs.write_resource::<RunState>();
             *runwriter = newrunstate;
         }
-        remove_the_dead(&mut self.ecs);
+        self.dispatch_events();
+    }
+}
+
+fn out_of_charges(ecs: &World, item: Entity) -> bool {
+    let empty = matches!(ecs.read_storage::<Charges>().get(item), Some(charges) if charges.current <= 0);
+    if empty {
+        let name = ecs.read_storage::<Name>().get(item).unwrap().name.clone();
+        ecs.write_resource::<GameLog>()
+            .entries
+            .insert(0, format!("The {} is out of charges.", name));
+    }
+    empty
+}
+
+fn trade(ecs: &mut World, vendor: Entity, item: Entity, mode: gui::VendorMode) {
+    let player_entity = *ecs.fetch::<Entity>();
+    let buyer = match mode {
+        gui::VendorMode::Buy => player_entity,
+        gui::VendorMode::Sell => vendor,
+    };
+    let price = ecs
+        .read_storage::<Item>()
+        .get(item)
+        .map_or(0, |stats| gui::trade_price(stats, mode));
+    let gold = ecs
+        .read_storage::<Purse>()
+        .get(player_entity)
+        .map_or(0, |purse| purse.gold);
+    if mode == gui::VendorMode::Buy && gold < price {
+        ecs.write_resource::<GameLog>()
+            .entries
+            .insert(0, "You can't afford that.".to_string());
+        return;
+    }
+
+    let stacked = matches!(
+        ecs.read_storage::<Stackable>().get(item),
+        Some(stack) if stack.quantity > 1
+    );
+    let item = if stacked { spawner::split_stack(ecs, item) } else { item };
+    let item_name = ecs.fetch::<Identification>().display_name(
+        item,
+        &ecs.read_storage::<Name>(),
+        &ecs.read_storage::<ObfuscatedName>(),
+    );
+
+    let entities = ecs.entities();
+    let names = ecs.read_storage::<Name>();
+    let mut in_backpack = ecs.write_storage::<InBackpack>();
+    let mut stackables = ecs.write_storage::<Stackable>();
+    let real_name = names.get(item).map(|name| name.name.clone());
+    let existing_stack = stackables.get(item).and_then(|_| {
+        (&entities, &in_backpack, &stackables, &names)
+            .join()
+            .find(|(stack, owned, _, name)| {
+                *stack != item && owned.owner == buyer && Some(&name.name) == real_name.as_ref()
+            })
+            .map(|(stack, _, _, _)| stack)
+    });
+    match existing_stack {
+        Some(stack) => {
+            if let Some(stack) = stackables.get_mut(stack) {
+                stack.quantity += 1;
+            }
+            entities.delete(item).expect("Unable to merge item into stack");
+        }
+        None => {
+            in_backpack
+                .insert(item, InBackpack { owner: buyer })
+                .expect("Unable to move item to new owner");
+        }
+    }
+
+    if let Some(purse) = ecs.write_storage::<Purse>().get_mut(player_entity) {
+        match mode {
+            gui::VendorMode::Buy => purse.gold -= price,
+            gui::VendorMode::Sell => purse.gold += price,
+        }
     }
+    let verb = match mode {
+        gui::VendorMode::Buy => "buy",
+        gui::VendorMode::Sell => "sell",
+    };
+    ecs.write_resource::<GameLog>()
+        .entries
+        .insert(0, format!("You {} {} for {} gold.", verb, item_name, price));
 }
 
 fn remove_the_dead(ecs: &mut World) {
@@ -172,23 +359,83 @@ fn remove_the_dead(ecs: &mut World) {
     {
         let combat_stats = ecs.read_storage::<CombatStats>();
         let players = ecs.read_storage::<Player>();
+        let mut dead_markers = ecs.write_storage::<Dead>();
         let entities = ecs.entities();
         let names = ecs.read_storage::<Name>();
-        let mut log = ecs.write_resource::<GameLog>();
-        for (entity, stats) in (&entities, &combat_stats).join() {
+        let mut events = ecs.write_resource::<GameEvents>();
+        let revived: Vec<Entity> = (&entities, &combat_stats, &dead_markers)
+            .join()
+            .filter(|(_, stats, _)| stats.hp > 0)
+            .map(|(entity, _, _)| entity)
+            .collect();
+        for entity in revived {
+            dead_markers.remove(entity);
+        }
+        for (entity, stats, _) in (&entities, &combat_stats, !&dead_markers).join() {
             if stats.hp < 1 {
-                let player = players.get(entity);
-                match player {
-                    None => {
-                        let victim_name = names.get(entity);
-                        log.entries
-                            .insert(0, format!("{} is dead", &victim_name.expect("Missing name").name));
-                        dead.push(entity)
-                    }
-                    Some(_) => log.entries.insert(0, "You are dead".to_string()),
+                let name = names.get(entity).expect("Missing name").name.clone();
+                events.publish(GameEvent::EntityDied { entity, name });
+                dead.push(entity);
+            }
+        }
+        for entity in dead.iter() {
+            dead_markers
+                .insert(*entity, Dead {})
+                .expect("Unable to mark entity dead");
+        }
+        dead.retain(|entity| players.get(*entity).is_none());
+    }
+    {
+        let entities = ecs.entities();
+        let status_effects = ecs.read_storage::<StatusEffect>();
+        for (effect_entity, effect) in (&entities, &status_effects).join() {
+            if dead.contains(&effect.target) {
+                entities.delete(effect_entity).expect("Could not delete status effect");
+            }
+        }
+        let abilities = ecs.read_storage::<Ability>();
+        for (ability_entity, ability) in (&entities, &abilities).join() {
+            if dead.contains(&ability.owner) {
+                entities.delete(ability_entity).expect("Could not delete ability");
+            }
+        }
+        let mut leaders = ecs.write_storage::<PackLeader>();
+        let mut followers = ecs.write_storage::<PackFollower>();
+        let dead_leaders: Vec<Entity> = dead.iter().copied().filter(|e| leaders.get(*e).is_some()).collect();
+        for leader in dead_leaders {
+            let pack: Vec<Entity> = (&entities, &followers)
+                .join()
+                .filter(|(follower, membership)| membership.leader == leader && !dead.contains(follower))
+                .map(|(follower, _)| follower)
+                .collect();
+            if let Some((new_leader, rest)) = pack.split_first() {
+                followers.remove(*new_leader);
+                if !rest.is_empty() {
+                    leaders
+                        .insert(*new_leader, PackLeader {})
+                        .expect("Unable to promote pack leader");
+                }
+                for follower in rest.iter() {
+                    followers
+                        .insert(*follower, PackFollower { leader: *new_leader })
+                        .expect("Unable to reassign pack follower");
                 }
             }
         }
+        let mut positions = ecs.write_storage::<Position>();
+        let mut in_backpack = ecs.write_storage::<InBackpack>();
+        let mut dropped = Vec::new();
+        for (item, carried) in (&entities, &in_backpack).join() {
+            if let Some(position) = positions.get(carried.owner) {
+                if dead.contains(&carried.owner) {
+                    dropped.push((item, *position));
+                }
+            }
+        }
+        for (item, position) in dropped {
+            in_backpack.remove(item);
+            positions.insert(item, position).expect("Unable to drop item");
+        }
     }
     for victim in dead {
         ecs.delete_entity(victim).expect("Could not delete dead entity");
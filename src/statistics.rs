@@ -0,0 +1,12 @@
+#[derive(Default)]
+pub struct Statistics {
+    pub kills: i32,
+    pub damage_taken: i32,
+    pub items_picked_up: i32,
+    pub deepest_level: i32,
+}
+
+#[derive(Default)]
+pub struct Achievements {
+    pub unlocked: Vec<&'static str>,
+}
@@ -0,0 +1,47 @@
+use crate::components::Equippable;
+use crate::events::{GameEvent, GameEvents};
+use crate::game_log::GameLog;
+use crate::statistics::Achievements;
+use specs::prelude::*;
+
+pub struct AchievementSystem {}
+
+const HEAVY_HIT: i32 = 10;
+
+impl<'a> System<'a> for AchievementSystem {
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, GameEvents>,
+        WriteExpect<'a, Achievements>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, Equippable>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_entity, events, mut achievements, mut gamelog, equippables) = data;
+
+        for event in events.events.iter() {
+            let achievement = match event {
+                GameEvent::EntityDied { entity, .. } if *entity != *player_entity => "First Blood",
+                GameEvent::EntityDamaged { entity, amount, .. }
+                    if *entity == *player_entity && *amount >= HEAVY_HIT =>
+                {
+                    "That Really Hurt"
+                }
+                GameEvent::ItemPickedUp { collector, item }
+                    if *collector == *player_entity && equippables.get(*item).is_some() =>
+                {
+                    "Gearing Up"
+                }
+                GameEvent::ItemPickedUp { collector, .. } if *collector == *player_entity => "Finders Keepers",
+                _ => continue,
+            };
+            if !achievements.unlocked.contains(&achievement) {
+                achievements.unlocked.push(achievement);
+                gamelog
+                    .entries
+                    .insert(0, format!("Achievement unlocked: {}!", achievement));
+            }
+        }
+    }
+}
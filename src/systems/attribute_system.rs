@@ -0,0 +1,79 @@
+use crate::components::{Attribute, AttributeBonus, Attributes, CombatStats, DerivedStats, Equipped, StatusEffect};
+use specs::prelude::*;
+
+pub struct AttributeSystem {}
+
+#[derive(SystemData)]
+pub struct AttributeSystemData<'a> {
+    entities: Entities<'a>,
+    attributes: WriteStorage<'a, Attributes>,
+    combat_stats: WriteStorage<'a, CombatStats>,
+    derived_stats: WriteStorage<'a, DerivedStats>,
+    equipped: ReadStorage<'a, Equipped>,
+    attribute_bonuses: ReadStorage<'a, AttributeBonus>,
+    status_effects: ReadStorage<'a, StatusEffect>,
+}
+
+impl AttributeSystem {
+    fn update(attribute: &mut Attribute, modifiers: i32) {
+        attribute.modifiers = modifiers;
+        attribute.bonus = (attribute.total() - 10) / 2;
+    }
+
+    fn modifiers(entity: Entity, data: &AttributeSystemData) -> AttributeBonus {
+        let mut modifiers = AttributeBonus::default();
+        for (equipped, bonus) in (&data.equipped, &data.attribute_bonuses).join() {
+            if equipped.owner == entity {
+                modifiers.add(bonus);
+            }
+        }
+        for effect in data.status_effects.join() {
+            if effect.target == entity {
+                modifiers.add(&effect.effect_type.attribute_modifiers());
+            }
+        }
+        modifiers
+    }
+}
+
+impl<'a> System<'a> for AttributeSystem {
+    type SystemData = AttributeSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        let dirty: Vec<Entity> = (&data.entities, &data.attributes)
+            .join()
+            .filter(|(_, attributes)| attributes.dirty)
+            .map(|(entity, _)| entity)
+            .collect();
+
+        for entity in dirty {
+            let modifiers = Self::modifiers(entity, &data);
+            let attributes = data.attributes.get_mut(entity).expect("Dirty entity has no attributes");
+            attributes.dirty = false;
+
+            Self::update(&mut attributes.might, modifiers.might);
+            Self::update(&mut attributes.fitness, modifiers.fitness);
+            Self::update(&mut attributes.quickness, modifiers.quickness);
+            Self::update(&mut attributes.intelligence, modifiers.intelligence);
+            let attributes = attributes.clone();
+
+            if let Some(stats) = data.combat_stats.get_mut(entity) {
+                stats.max_hp = i32::max(1, attributes.fitness.total() * 2);
+                stats.hp = i32::min(stats.hp, stats.max_hp);
+            }
+
+            data.derived_stats
+                .insert(
+                    entity,
+                    DerivedStats {
+                        to_hit: attributes.might.bonus,
+                        carry_capacity: (attributes.might.total() + 10) * 5,
+                        initiative: attributes.quickness.bonus,
+                        stealth: attributes.quickness.bonus,
+                        regen_interval: i32::max(1, 10 - attributes.fitness.bonus * 2),
+                    },
+                )
+                .expect("Unable to insert derived stats");
+        }
+    }
+}
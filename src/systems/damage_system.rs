@@ -1,15 +1,74 @@
-use crate::components::{CombatStats, SufferDamage};
+use crate::components::{CombatStats, Immune, Name, Position, Resistant, SufferDamage, Vulnerable};
+use crate::events::{GameEvent, GameEvents};
+use crate::game_log::GameLog;
+use crate::noise::Noises;
+use rltk::Point;
 use specs::prelude::*;
 
 pub struct DamageSystem {}
 
+const PAIN_NOISE: i32 = 4;
+
 impl<'a> System<'a> for DamageSystem {
-    type SystemData = (WriteStorage<'a, CombatStats>, WriteStorage<'a, SufferDamage>);
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, CombatStats>,
+        WriteStorage<'a, SufferDamage>,
+        ReadStorage<'a, Resistant>,
+        ReadStorage<'a, Vulnerable>,
+        ReadStorage<'a, Immune>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, Position>,
+        WriteExpect<'a, Noises>,
+        WriteExpect<'a, GameEvents>,
+    );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut combat_stats, mut suffer_damage) = data;
-        for (mut stats, damage) in (&mut combat_stats, &suffer_damage).join() {
-            stats.hp -= damage.amount
+        let (
+            entities,
+            mut combat_stats,
+            mut suffer_damage,
+            resistant,
+            vulnerable,
+            immune,
+            names,
+            mut log,
+            positions,
+            mut noises,
+            mut events,
+        ) = data;
+
+        for (entity, stats, damage) in (&entities, &mut combat_stats, &suffer_damage).join() {
+            for (amount, damage_type) in damage.amount.iter() {
+                let mut amount = *amount;
+                let mut outcome = None;
+                if matches!(immune.get(entity), Some(i) if i.damage_types.contains(damage_type)) {
+                    amount = 0;
+                    outcome = Some("is immune to");
+                } else if matches!(resistant.get(entity), Some(r) if r.damage_types.contains(damage_type)) {
+                    amount /= 2;
+                    outcome = Some("resists");
+                } else if matches!(vulnerable.get(entity), Some(v) if v.damage_types.contains(damage_type)) {
+                    amount *= 2;
+                    outcome = Some("is vulnerable to");
+                }
+
+                if let (Some(outcome), Some(name)) = (outcome, names.get(entity)) {
+                    log.entries
+                        .insert(0, format!("{} {} {} damage.", name.name, outcome, damage_type.name()));
+                }
+                stats.hp -= amount;
+                events.publish(GameEvent::EntityDamaged {
+                    entity,
+                    amount,
+                    damage_type: *damage_type,
+                });
+            }
+            if let Some(pos) = positions.get(entity) {
+                noises.emit(Point::new(pos.x, pos.y), PAIN_NOISE);
+            }
         }
 
         suffer_damage.clear();
@@ -0,0 +1,419 @@
+use crate::components::{
+    Attributes, Charges, CombatStats, Confusion, Consumable, Cursed, Equipped, HungerClock, HungerState, InBackpack,
+    InflictsDamage, InflictsStatus, Name, ObfuscatedName, Position, ProvidesFood, ProvidesHealing,
+    ProvidesIdentification, ProvidesMagicMapping, ProvidesRecharge, ProvidesRemoveCurse, ProvidesTeleport, Stackable,
+    StatusEffect, StatusEffectType, SufferDamage, Viewshed,
+};
+use crate::effects::{Effect, EffectQueue, EffectType, Targets};
+use crate::game_log::GameLog;
+use crate::identification::Identification;
+use crate::map::{Map, TileType};
+use crate::state::RunState;
+use rltk::{Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+pub struct EffectSystem {}
+
+const TELEPORT_ATTEMPTS: i32 = 50;
+
+#[derive(SystemData)]
+pub struct EffectSystemData<'a> {
+    player_entity: ReadExpect<'a, Entity>,
+    effect_queue: WriteExpect<'a, EffectQueue>,
+    gamelog: WriteExpect<'a, GameLog>,
+    map: ReadExpect<'a, Map>,
+    runstate: WriteExpect<'a, RunState>,
+    rng: WriteExpect<'a, RandomNumberGenerator>,
+    player_position: WriteExpect<'a, Point>,
+    identification: WriteExpect<'a, Identification>,
+    lazy: Read<'a, LazyUpdate>,
+    entities: Entities<'a>,
+    names: ReadStorage<'a, Name>,
+    obfuscated_names: ReadStorage<'a, ObfuscatedName>,
+    consumables: ReadStorage<'a, Consumable>,
+    stackables: WriteStorage<'a, Stackable>,
+    charges: WriteStorage<'a, Charges>,
+    provides_healing: ReadStorage<'a, ProvidesHealing>,
+    provides_food: ReadStorage<'a, ProvidesFood>,
+    inflicts_damage: ReadStorage<'a, InflictsDamage>,
+    confusion: ReadStorage<'a, Confusion>,
+    inflicts_status: ReadStorage<'a, InflictsStatus>,
+    provides_identification: ReadStorage<'a, ProvidesIdentification>,
+    provides_recharge: ReadStorage<'a, ProvidesRecharge>,
+    provides_remove_curse: ReadStorage<'a, ProvidesRemoveCurse>,
+    provides_magic_mapping: ReadStorage<'a, ProvidesMagicMapping>,
+    provides_teleport: ReadStorage<'a, ProvidesTeleport>,
+    combat_stats: WriteStorage<'a, CombatStats>,
+    suffer_damage: WriteStorage<'a, SufferDamage>,
+    status_effects: WriteStorage<'a, StatusEffect>,
+    hunger_clocks: WriteStorage<'a, HungerClock>,
+    in_backpack: ReadStorage<'a, InBackpack>,
+    equipped: ReadStorage<'a, Equipped>,
+    cursed: WriteStorage<'a, Cursed>,
+    positions: WriteStorage<'a, Position>,
+    viewsheds: WriteStorage<'a, Viewshed>,
+    attributes: WriteStorage<'a, Attributes>,
+}
+
+impl EffectSystem {
+    fn resolve(targets: &Targets, map: &Map) -> Vec<Entity> {
+        match targets {
+            Targets::Single { target } => vec![*target],
+            Targets::Entities { targets } => targets.clone(),
+            Targets::Tile { tile } => map.tile_content[map.point_to_idx(*tile)].clone(),
+            Targets::Area { tiles } => tiles
+                .iter()
+                .flat_map(|tile| map.tile_content[map.point_to_idx(*tile)].iter().copied())
+                .collect(),
+        }
+    }
+
+    fn apply(effect: Effect, data: &mut EffectSystemData) {
+        let targets = Self::resolve(&effect.targets, &data.map);
+        match effect.effect_type {
+            EffectType::ItemUse { item } => Self::item_trigger(effect.creator, item, targets, data),
+            effect_type => {
+                for target in targets {
+                    Self::apply_to(effect_type, target, data);
+                }
+            }
+        }
+    }
+
+    fn apply_to(effect_type: EffectType, target: Entity, data: &mut EffectSystemData) {
+        match effect_type {
+            EffectType::Damage { amount, damage_type } => {
+                SufferDamage::new_damage(&mut data.suffer_damage, target, amount, damage_type)
+            }
+            EffectType::Healing { amount } => {
+                if let Some(stats) = data.combat_stats.get_mut(target) {
+                    stats.hp = i32::min(stats.max_hp, stats.hp + amount);
+                }
+            }
+            EffectType::Status { effect_type, turns } => {
+                StatusEffect::add(
+                    &data.lazy,
+                    &data.entities,
+                    &mut data.status_effects,
+                    target,
+                    effect_type,
+                    turns,
+                );
+                Attributes::invalidate(&mut data.attributes, target);
+            }
+            EffectType::WellFed => {
+                if let Some(clock) = data.hunger_clocks.get_mut(target) {
+                    clock.state = HungerState::WellFed;
+                    clock.duration = HungerClock::WELL_FED_DURATION;
+                }
+            }
+            EffectType::Identify => Self::identify(target, data),
+            EffectType::Recharge => Self::recharge(target, data),
+            EffectType::RemoveCurse => Self::remove_curse(target, data),
+            EffectType::MagicMapping => {
+                if target == *data.player_entity {
+                    data.gamelog
+                        .entries
+                        .insert(0, "The layout of the level floods into your mind!".to_string());
+                    *data.runstate = RunState::MagicMapReveal { row: 0 };
+                }
+            }
+            EffectType::Teleport => Self::teleport(target, data),
+            EffectType::ItemUse { .. } => unreachable!("Item use resolves against all of its targets at once"),
+        }
+    }
+
+    fn item_trigger(creator: Option<Entity>, item: Entity, targets: Vec<Entity>, data: &mut EffectSystemData) {
+        let activation = ItemActivation {
+            creator,
+            item,
+            by_player: creator == Some(*data.player_entity),
+            item_name: data
+                .identification
+                .display_name(item, &data.names, &data.obfuscated_names),
+            targets,
+        };
+        for trigger in ITEM_TRIGGERS.iter() {
+            trigger(&activation, data);
+        }
+
+        if let Some(charges) = data.charges.get_mut(item) {
+            charges.current -= 1;
+        }
+        match (data.consumables.get(item), data.stackables.get_mut(item)) {
+            (None, _) => {}
+            (Some(_), Some(stack)) if stack.quantity > 1 => stack.quantity -= 1,
+            (Some(_), _) => data
+                .entities
+                .delete(item)
+                .expect("Entity removal for consumables failed"),
+        }
+    }
+
+    fn identify(owner: Entity, data: &mut EffectSystemData) {
+        let mut identified = Vec::new();
+        for (carried, in_backpack, name) in (&data.entities, &data.in_backpack, &data.names).join() {
+            if in_backpack.owner == owner
+                && data.obfuscated_names.get(carried).is_some()
+                && data.identification.identify(&name.name)
+            {
+                identified.push(name.name.clone());
+            }
+        }
+        if owner == *data.player_entity {
+            if identified.is_empty() {
+                data.gamelog.entries.insert(0, "You learn nothing new.".to_string());
+            }
+            for name in identified {
+                data.gamelog.entries.insert(0, format!("You identify the {}.", name));
+            }
+        }
+    }
+
+    fn recharge(owner: Entity, data: &mut EffectSystemData) {
+        for (in_backpack, charges, name) in (&data.in_backpack, &mut data.charges, &data.names).join() {
+            if in_backpack.owner == owner {
+                charges.current = charges.max;
+                if owner == *data.player_entity {
+                    data.gamelog.entries.insert(0, format!("Your {} glows.", name.name));
+                }
+            }
+        }
+    }
+
+    fn remove_curse(owner: Entity, data: &mut EffectSystemData) {
+        let mut lifted = Vec::new();
+        for (carried, _cursed) in (&data.entities, &data.cursed).join() {
+            let owned = matches!(data.equipped.get(carried), Some(equipped) if equipped.owner == owner)
+                || matches!(data.in_backpack.get(carried), Some(in_backpack) if in_backpack.owner == owner);
+            if owned {
+                lifted.push(carried);
+            }
+        }
+        if owner == *data.player_entity {
+            if lifted.is_empty() {
+                data.gamelog.entries.insert(0, "Nothing seems to happen.".to_string());
+            }
+            for carried in lifted.iter() {
+                let name = &data.names.get(*carried).unwrap().name;
+                data.gamelog.entries.insert(0, format!("Your {} glows white.", name));
+            }
+        }
+        if !lifted.is_empty() {
+            Attributes::invalidate(&mut data.attributes, owner);
+        }
+        for carried in lifted {
+            data.cursed.remove(carried);
+        }
+    }
+
+    fn teleport_destination(map: &Map, rng: &mut RandomNumberGenerator, from: Point) -> Option<Point> {
+        for _ in 0..TELEPORT_ATTEMPTS {
+            let idx = rng.range(0, map.dimensions as i32) as usize;
+            if map.tiles[idx] != TileType::Floor || map.blocked[idx] {
+                continue;
+            }
+            let path = rltk::a_star_search(map.point_to_idx(from) as i32, idx as i32, map);
+            if path.success {
+                let (x, y) = map.idx_to_xy(idx);
+                return Some(Point::new(x, y));
+            }
+        }
+        None
+    }
+
+    fn teleport(target: Entity, data: &mut EffectSystemData) {
+        let from = match data.positions.get(target) {
+            Some(pos) => Point::new(pos.x, pos.y),
+            None => return,
+        };
+        let destination = match Self::teleport_destination(&data.map, &mut data.rng, from) {
+            Some(destination) => destination,
+            None => return,
+        };
+        if let Some(pos) = data.positions.get_mut(target) {
+            pos.x = destination.x;
+            pos.y = destination.y;
+        }
+        if let Some(viewshed) = data.viewsheds.get_mut(target) {
+            viewshed.dirty = true;
+        }
+        if target == *data.player_entity {
+            *data.player_position = destination;
+            data.gamelog
+                .entries
+                .insert(0, "You are yanked through space!".to_string());
+        } else if let Some(name) = data.names.get(target) {
+            data.gamelog.entries.insert(0, format!("{} vanishes!", name.name));
+        }
+    }
+}
+
+impl<'a> System<'a> for EffectSystem {
+    type SystemData = EffectSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        while let Some(effect) = data.effect_queue.effects.pop_front() {
+            Self::apply(effect, &mut data);
+        }
+    }
+}
+
+struct ItemActivation {
+    creator: Option<Entity>,
+    item: Entity,
+    by_player: bool,
+    item_name: String,
+    targets: Vec<Entity>,
+}
+
+impl ItemActivation {
+    fn affected(&self) -> Targets {
+        Targets::Entities {
+            targets: self.targets.clone(),
+        }
+    }
+
+    fn enqueue(&self, effect_type: EffectType, targets: Targets, data: &mut EffectSystemData) {
+        data.effect_queue.add_effect(self.creator, effect_type, targets);
+    }
+
+    fn enqueue_for_user(&self, effect_type: EffectType, data: &mut EffectSystemData) {
+        if let Some(user) = self.creator {
+            self.enqueue(effect_type, Targets::Single { target: user }, data);
+        }
+    }
+}
+
+type ItemTrigger = fn(&ItemActivation, &mut EffectSystemData);
+
+const ITEM_TRIGGERS: [ItemTrigger; 10] = [
+    trigger_healing,
+    trigger_food,
+    trigger_damage,
+    trigger_confusion,
+    trigger_status,
+    trigger_identification,
+    trigger_recharge,
+    trigger_remove_curse,
+    trigger_magic_mapping,
+    trigger_teleport,
+];
+
+fn trigger_healing(activation: &ItemActivation, data: &mut EffectSystemData) {
+    let heal_amount = match data.provides_healing.get(activation.item) {
+        Some(healing) => healing.heal_amount,
+        None => return,
+    };
+    for target in activation.targets.iter() {
+        if activation.by_player && data.combat_stats.get(*target).is_some() {
+            data.gamelog.entries.insert(
+                0,
+                format!("You use the {}, healing {}", activation.item_name, heal_amount),
+            );
+        }
+    }
+    activation.enqueue(EffectType::Healing { amount: heal_amount }, activation.affected(), data);
+}
+
+fn trigger_food(activation: &ItemActivation, data: &mut EffectSystemData) {
+    if data.provides_food.get(activation.item).is_none() {
+        return;
+    }
+    for target in activation.targets.iter() {
+        if activation.by_player && data.hunger_clocks.get(*target).is_some() {
+            data.gamelog
+                .entries
+                .insert(0, format!("You eat the {}.", activation.item_name));
+        }
+    }
+    activation.enqueue(EffectType::WellFed, activation.affected(), data);
+}
+
+fn trigger_damage(activation: &ItemActivation, data: &mut EffectSystemData) {
+    let (amount, damage_type) = match data.inflicts_damage.get(activation.item) {
+        Some(damage) => (damage.damage, damage.damage_type),
+        None => return,
+    };
+    for target in activation.targets.iter() {
+        if activation.by_player {
+            let target_name = data.names.get(*target).map_or("something", |name| &name.name);
+            data.gamelog.entries.insert(
+                0,
+                format!(
+                    "You use {} on {}, inflicting {} hp.",
+                    activation.item_name, target_name, amount
+                ),
+            );
+        } else if *target == *data.player_entity {
+            data.gamelog
+                .entries
+                .insert(0, format!("{} hits you, for {} hp.", activation.item_name, amount));
+        }
+    }
+    activation.enqueue(EffectType::Damage { amount, damage_type }, activation.affected(), data);
+}
+
+fn trigger_confusion(activation: &ItemActivation, data: &mut EffectSystemData) {
+    if let Some(turns) = data.confusion.get(activation.item).map(|confuses| confuses.turns) {
+        inflict_status(activation, StatusEffectType::Confused, turns, data);
+    }
+}
+
+fn trigger_status(activation: &ItemActivation, data: &mut EffectSystemData) {
+    if let Some(status) = data.inflicts_status.get(activation.item).cloned() {
+        inflict_status(activation, status.effect_type, status.turns, data);
+    }
+}
+
+fn inflict_status(activation: &ItemActivation, effect_type: StatusEffectType, turns: i32, data: &mut EffectSystemData) {
+    for target in activation.targets.iter() {
+        if *target == *data.player_entity {
+            data.gamelog
+                .entries
+                .insert(0, format!("You are {}.", effect_type.name()));
+        } else if activation.by_player {
+            let target_name = data.names.get(*target).map_or("something", |name| &name.name);
+            data.gamelog.entries.insert(
+                0,
+                format!(
+                    "You use {} on {}, leaving it {}.",
+                    activation.item_name,
+                    target_name,
+                    effect_type.name()
+                ),
+            );
+        }
+    }
+    activation.enqueue(EffectType::Status { effect_type, turns }, activation.affected(), data);
+}
+
+fn trigger_identification(activation: &ItemActivation, data: &mut EffectSystemData) {
+    if data.provides_identification.get(activation.item).is_some() {
+        activation.enqueue_for_user(EffectType::Identify, data);
+    }
+}
+
+fn trigger_recharge(activation: &ItemActivation, data: &mut EffectSystemData) {
+    if data.provides_recharge.get(activation.item).is_some() {
+        activation.enqueue_for_user(EffectType::Recharge, data);
+    }
+}
+
+fn trigger_remove_curse(activation: &ItemActivation, data: &mut EffectSystemData) {
+    if data.provides_remove_curse.get(activation.item).is_some() {
+        activation.enqueue_for_user(EffectType::RemoveCurse, data);
+    }
+}
+
+fn trigger_magic_mapping(activation: &ItemActivation, data: &mut EffectSystemData) {
+    if data.provides_magic_mapping.get(activation.item).is_some() {
+        activation.enqueue_for_user(EffectType::MagicMapping, data);
+    }
+}
+
+fn trigger_teleport(activation: &ItemActivation, data: &mut EffectSystemData) {
+    if data.provides_teleport.get(activation.item).is_some() {
+        activation.enqueue(EffectType::Teleport, activation.affected(), data);
+    }
+}
@@ -0,0 +1,63 @@
+use crate::components::{Burden, DerivedStats, Equipped, InBackpack, Item, Stackable};
+use crate::game_log::GameLog;
+use specs::prelude::*;
+use std::collections::HashMap;
+
+pub struct EncumbranceSystem {}
+
+impl<'a> System<'a> for EncumbranceSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
+        Entities<'a>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, InBackpack>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, Stackable>,
+        ReadStorage<'a, DerivedStats>,
+        WriteStorage<'a, Burden>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            player_entity,
+            mut gamelog,
+            entities,
+            items,
+            in_backpack,
+            equipped,
+            stackables,
+            derived_stats,
+            mut burdens,
+        ) = data;
+
+        let mut carried: HashMap<Entity, i32> = HashMap::new();
+        for (entity, item) in (&entities, &items).join() {
+            let owner = match (in_backpack.get(entity), equipped.get(entity)) {
+                (Some(in_backpack), _) => in_backpack.owner,
+                (None, Some(equipped)) => equipped.owner,
+                (None, None) => continue,
+            };
+            let quantity = stackables.get(entity).map_or(1, |stack| stack.quantity);
+            *carried.entry(owner).or_insert(0) += item.weight * quantity;
+        }
+
+        for (entity, derived) in (&entities, &derived_stats).join() {
+            let burden = Burden {
+                carried: carried.get(&entity).copied().unwrap_or(0),
+                capacity: derived.carry_capacity,
+            };
+            let was_overburdened = matches!(burdens.get(entity), Some(old) if old.overburdened());
+            if entity == *player_entity && burden.overburdened() != was_overburdened {
+                let message = if burden.overburdened() {
+                    "You are overburdened."
+                } else {
+                    "You are no longer overburdened."
+                };
+                gamelog.entries.insert(0, message.to_string());
+            }
+            burdens.insert(entity, burden).expect("Unable to insert burden");
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use crate::events::{GameEvent, GameEvents};
+use crate::game_log::GameLog;
+use specs::prelude::*;
+
+pub struct EventLogSystem {}
+
+impl<'a> System<'a> for EventLogSystem {
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, GameEvents>,
+        WriteExpect<'a, GameLog>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_entity, events, mut gamelog) = data;
+
+        for event in events.events.iter() {
+            match event {
+                GameEvent::EntityDied { entity, .. } if *entity == *player_entity => {
+                    gamelog.entries.insert(0, "You are dead".to_string())
+                }
+                GameEvent::EntityDied { name, .. } => gamelog.entries.insert(0, format!("{} is dead", name)),
+                GameEvent::LevelEntered { depth } => gamelog
+                    .entries
+                    .insert(0, format!("You enter level {} of the dungeon.", depth)),
+                _ => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use crate::components::{Ai, AiState, Monster, Name, Position};
+use crate::game_log::GameLog;
+use crate::map::Map;
+use crate::noise::Noises;
+use rltk::{DistanceAlg, Point};
+use specs::prelude::*;
+
+pub struct HearingSystem {}
+
+impl<'a> System<'a> for HearingSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteExpect<'a, Noises>,
+        WriteExpect<'a, GameLog>,
+        ReadExpect<'a, Map>,
+        Entities<'a>,
+        ReadStorage<'a, Monster>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, Ai>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut noises, mut log, map, entities, monsters, positions, names, mut ais) = data;
+
+        for (entity, _monster, pos, name, ai) in (&entities, &monsters, &positions, &names, &mut ais).join() {
+            if ai.state != AiState::Sleep && ai.state != AiState::Wander {
+                continue;
+            }
+
+            let here = Point::new(pos.x, pos.y);
+            let heard = noises
+                .sounds
+                .iter()
+                .find(|noise| DistanceAlg::Pythagoras.distance2d(here, noise.origin) <= noise.radius as f32);
+
+            if let Some(noise) = heard {
+                if ai.state == AiState::Sleep && map.visible_tiles[map.xy_to_idx(pos.x, pos.y)] {
+                    log.entries.insert(0, format!("{} wakes up.", name.name));
+                }
+                ai.transition(AiState::Search, entity, Some(name));
+                ai.last_seen_target = Some(noise.origin);
+                ai.destination = None;
+            }
+        }
+
+        noises.sounds.clear();
+    }
+}
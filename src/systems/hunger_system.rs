@@ -0,0 +1,70 @@
+use crate::components::{DamageType, HungerClock, HungerState, MyTurn};
+use crate::effects::{EffectQueue, EffectType, Targets};
+use crate::game_log::GameLog;
+use crate::state::RunState;
+use specs::prelude::*;
+
+pub struct HungerSystem {}
+
+const STARVATION_DAMAGE: i32 = 1;
+
+#[derive(SystemData)]
+pub struct HungerSystemData<'a> {
+    player_entity: ReadExpect<'a, Entity>,
+    runstate: ReadExpect<'a, RunState>,
+    gamelog: WriteExpect<'a, GameLog>,
+    entities: Entities<'a>,
+    hunger_clocks: WriteStorage<'a, HungerClock>,
+    my_turns: ReadStorage<'a, MyTurn>,
+    effect_queue: WriteExpect<'a, EffectQueue>,
+}
+
+impl<'a> System<'a> for HungerSystem {
+    type SystemData = HungerSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        if *data.runstate != RunState::Ticking && *data.runstate != RunState::AwaitingInput {
+            return;
+        }
+
+        for (entity, clock, _my_turn) in (&data.entities, &mut data.hunger_clocks, &data.my_turns).join() {
+            clock.duration -= 1;
+            if clock.duration > 0 {
+                continue;
+            }
+
+            let message = match clock.state {
+                HungerState::WellFed => {
+                    clock.state = HungerState::Normal;
+                    clock.duration = HungerClock::STAGE_DURATION;
+                    "You are no longer well fed."
+                }
+                HungerState::Normal => {
+                    clock.state = HungerState::Hungry;
+                    clock.duration = HungerClock::STAGE_DURATION;
+                    "You are hungry."
+                }
+                HungerState::Hungry => {
+                    clock.state = HungerState::Starving;
+                    clock.duration = HungerClock::STARVATION_INTERVAL;
+                    "You are starving!"
+                }
+                HungerState::Starving => {
+                    clock.duration = HungerClock::STARVATION_INTERVAL;
+                    data.effect_queue.add_effect(
+                        None,
+                        EffectType::Damage {
+                            amount: STARVATION_DAMAGE,
+                            damage_type: DamageType::Starvation,
+                        },
+                        Targets::Single { target: entity },
+                    );
+                    "Your hunger pangs are getting painful!"
+                }
+            };
+            if entity == *data.player_entity {
+                data.gamelog.entries.insert(0, message.to_string());
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate::components::{Burden, DerivedStats, Energy, MyTurn, StatusEffect, StatusEffectType};
+use crate::state::RunState;
+use specs::prelude::*;
+
+pub struct InitiativeSystem {}
+
+impl<'a> System<'a> for InitiativeSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, RunState>,
+        Entities<'a>,
+        WriteStorage<'a, Energy>,
+        WriteStorage<'a, MyTurn>,
+        ReadStorage<'a, DerivedStats>,
+        ReadStorage<'a, StatusEffect>,
+        ReadStorage<'a, Burden>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_entity, mut runstate, entities, mut energies, mut my_turns, derived_stats, status_effects, burdens) =
+            data;
+
+        if *runstate != RunState::Ticking {
+            return;
+        }
+
+        for (entity, energy) in (&entities, &mut energies).join() {
+            let mut speed = energy.speed + derived_stats.get(entity).map_or(0, |derived| derived.initiative);
+            if StatusEffect::is_affected(&status_effects, entity, StatusEffectType::Slowed) {
+                speed /= 2;
+            }
+            if matches!(burdens.get(entity), Some(burden) if burden.overburdened()) {
+                speed /= 2;
+            }
+            energy.energy += i32::max(1, speed);
+
+            if energy.energy >= Energy::ACTION_COST {
+                my_turns.insert(entity, MyTurn {}).expect("Unable to insert turn");
+                if entity == *player_entity {
+                    *runstate = RunState::AwaitingInput;
+                }
+            }
+        }
+    }
+}
@@ -1,94 +1,191 @@
 use crate::components::{
-    AreaOfEffect, CombatStats, Confusion, Consumable, InBackpack, InflictsDamage, Name, Position, ProvidesHealing,
-    SufferDamage, WantsToDropItem, WantsToPickupItem, WantsToUseItem,
+    AreaOfEffect, Attributes, Charges, Cursed, EquipmentSlot, Equippable, Equipped, Gold, InBackpack, Name,
+    ObfuscatedName, Position, Purse, Stackable, WantsToDropItem, WantsToPickupItem, WantsToRemoveItem, WantsToUseItem,
 };
+use crate::effects::{EffectQueue, EffectType, Targets};
+use crate::events::{GameEvent, GameEvents};
 use crate::game_log::GameLog;
+use crate::identification::Identification;
 use crate::map::Map;
 use specs::prelude::*;
 
 pub struct ItemCollectionSystem {}
 pub struct ItemUseSystem {}
 pub struct ItemDropSystem {}
+pub struct ItemRemoveSystem {}
+
+#[derive(SystemData)]
+pub struct ItemCollectionSystemData<'a> {
+    player_entity: ReadExpect<'a, Entity>,
+    gamelog: WriteExpect<'a, GameLog>,
+    identification: ReadExpect<'a, Identification>,
+    entities: Entities<'a>,
+    wants_to_pickup: WriteStorage<'a, WantsToPickupItem>,
+    positions: WriteStorage<'a, Position>,
+    names: ReadStorage<'a, Name>,
+    obfuscated_names: ReadStorage<'a, ObfuscatedName>,
+    in_backpack: WriteStorage<'a, InBackpack>,
+    stackables: WriteStorage<'a, Stackable>,
+    gold: ReadStorage<'a, Gold>,
+    purses: WriteStorage<'a, Purse>,
+    events: WriteExpect<'a, GameEvents>,
+}
 
 #[derive(SystemData)]
 pub struct ItemUseSystemData<'a> {
     player_entity: ReadExpect<'a, Entity>,
     gamelog: WriteExpect<'a, GameLog>,
     map: ReadExpect<'a, Map>,
+    effect_queue: WriteExpect<'a, EffectQueue>,
+    identification: WriteExpect<'a, Identification>,
     entities: Entities<'a>,
     wants_to_use_item: WriteStorage<'a, WantsToUseItem>,
     names: ReadStorage<'a, Name>,
-    consumables: ReadStorage<'a, Consumable>,
-    provides_healing: ReadStorage<'a, ProvidesHealing>,
-    inflicts_damage: ReadStorage<'a, InflictsDamage>,
-    combat_stats: WriteStorage<'a, CombatStats>,
-    suffer_damage: WriteStorage<'a, SufferDamage>,
-    area_of_effect: WriteStorage<'a, AreaOfEffect>,
-    confusion: WriteStorage<'a, Confusion>,
+    area_of_effect: ReadStorage<'a, AreaOfEffect>,
+    equippable: ReadStorage<'a, Equippable>,
+    equipped: WriteStorage<'a, Equipped>,
+    in_backpack: WriteStorage<'a, InBackpack>,
+    charges: ReadStorage<'a, Charges>,
+    cursed: WriteStorage<'a, Cursed>,
+    attributes: WriteStorage<'a, Attributes>,
+}
+
+impl ItemCollectionSystem {
+    fn existing_stack(pickup: &WantsToPickupItem, data: &ItemCollectionSystemData) -> Option<Entity> {
+        data.stackables.get(pickup.item)?;
+        let name = &data.names.get(pickup.item)?.name;
+        (&data.entities, &data.in_backpack, &data.stackables, &data.names)
+            .join()
+            .find(|(stack, in_backpack, _, stack_name)| {
+                *stack != pickup.item && in_backpack.owner == pickup.collected_by && stack_name.name == *name
+            })
+            .map(|(stack, _, _, _)| stack)
+    }
 }
 
 impl<'a> System<'a> for ItemCollectionSystem {
-    #[allow(clippy::type_complexity)]
-    type SystemData = (
-        ReadExpect<'a, Entity>,
-        WriteExpect<'a, GameLog>,
-        WriteStorage<'a, WantsToPickupItem>,
-        WriteStorage<'a, Position>,
-        ReadStorage<'a, Name>,
-        WriteStorage<'a, InBackpack>,
-    );
+    type SystemData = ItemCollectionSystemData<'a>;
 
-    fn run(&mut self, data: Self::SystemData) {
-        let (player_entity, mut gamelog, mut wants_to_pickup, mut positions, names, mut in_backpack) = data;
+    fn run(&mut self, mut data: Self::SystemData) {
+        for pickup in data.wants_to_pickup.join() {
+            data.positions.remove(pickup.item);
+            data.events.publish(GameEvent::ItemPickedUp {
+                collector: pickup.collected_by,
+                item: pickup.item,
+            });
+            if let (Some(gold), Some(purse)) = (data.gold.get(pickup.item), data.purses.get_mut(pickup.collected_by)) {
+                purse.gold += gold.amount;
+                if pickup.collected_by == *data.player_entity {
+                    data.gamelog
+                        .entries
+                        .insert(0, format!("You pick up {} gold.", gold.amount));
+                }
+                data.entities.delete(pickup.item).expect("Unable to collect gold");
+                continue;
+            }
+            match Self::existing_stack(pickup, &data) {
+                Some(stack) => {
+                    let quantity = data.stackables.get(pickup.item).map_or(1, |picked| picked.quantity);
+                    if let Some(stack) = data.stackables.get_mut(stack) {
+                        stack.quantity += quantity;
+                    }
+                    data.entities
+                        .delete(pickup.item)
+                        .expect("Unable to merge item into stack");
+                }
+                None => {
+                    data.in_backpack
+                        .insert(
+                            pickup.item,
+                            InBackpack {
+                                owner: pickup.collected_by,
+                            },
+                        )
+                        .expect("Unable to insert item to backpack");
+                }
+            }
 
-        for pickup in wants_to_pickup.join() {
-            positions.remove(pickup.item);
-            in_backpack
-                .insert(
-                    pickup.item,
-                    InBackpack {
-                        owner: pickup.collected_by,
-                    },
-                )
-                .expect("Unable to insert item to backpack");
-
-            if pickup.collected_by == *player_entity {
-                gamelog
-                    .entries
-                    .insert(0, format!("You pick up {}", names.get(pickup.item).unwrap().name));
+            if pickup.collected_by == *data.player_entity {
+                let item_name = data
+                    .identification
+                    .display_name(pickup.item, &data.names, &data.obfuscated_names);
+                data.gamelog.entries.insert(0, format!("You pick up {}", item_name));
             }
         }
 
-        wants_to_pickup.clear();
+        data.wants_to_pickup.clear();
     }
 }
 
 impl ItemUseSystem {
-    fn targets(used_item: &WantsToUseItem, data: &<ItemUseSystem as System>::SystemData) -> Vec<Entity> {
-        let mut action_targets: Vec<Entity> = Vec::new();
-
+    fn targets(user: Entity, used_item: &WantsToUseItem, data: &<ItemUseSystem as System>::SystemData) -> Targets {
         match used_item.target {
-            None => action_targets.push(*data.player_entity),
+            None => Targets::Single { target: user },
             Some(target) => match data.area_of_effect.get(used_item.item) {
-                None => {
-                    let idx = data.map.point_to_idx(target);
-                    for mob in data.map.tile_content[idx].iter() {
-                        action_targets.push(*mob);
-                    }
-                }
+                None => Targets::Tile { tile: target },
                 Some(area_of_effect) => {
                     let mut blast = rltk::field_of_view(target, area_of_effect.radius, &*data.map);
                     blast.retain(|p| p.x > 0 && p.x < data.map.width - 1 && p.y > 0 && p.y < data.map.height - 1);
-                    for point in blast.iter() {
-                        let idx = data.map.point_to_idx(*point);
-                        for mob in data.map.tile_content[idx].iter() {
-                            action_targets.push(*mob)
-                        }
-                    }
+                    Targets::Area { tiles: blast }
                 }
             },
         }
-        action_targets
+    }
+}
+
+impl ItemUseSystem {
+    fn equip(entity: Entity, item: Entity, slot: EquipmentSlot, data: &mut ItemUseSystemData) {
+        let stuck = (&data.entities, &data.equipped, &mut data.cursed)
+            .join()
+            .find(|(_, equipped, _)| equipped.owner == entity && equipped.slot == slot);
+        if let Some((stuck_item, _, cursed)) = stuck {
+            cursed.revealed = true;
+            Attributes::invalidate(&mut data.attributes, entity);
+            if entity == *data.player_entity {
+                let stuck_name = &data.names.get(stuck_item).unwrap().name;
+                data.gamelog
+                    .entries
+                    .insert(0, format!("You can't remove the cursed {}!", stuck_name));
+            }
+            return;
+        }
+
+        let mut to_unequip = Vec::new();
+        for (worn_item, equipped, name) in (&data.entities, &data.equipped, &data.names).join() {
+            if equipped.owner == entity && equipped.slot == slot {
+                to_unequip.push(worn_item);
+                if entity == *data.player_entity {
+                    data.gamelog.entries.insert(0, format!("You unequip {}.", name.name));
+                }
+            }
+        }
+        for worn_item in to_unequip.iter() {
+            data.equipped.remove(*worn_item);
+            data.in_backpack
+                .insert(*worn_item, InBackpack { owner: entity })
+                .expect("Unable to insert unequipped item to backpack");
+        }
+
+        data.equipped
+            .insert(item, Equipped { owner: entity, slot })
+            .expect("Unable to equip item");
+        data.in_backpack.remove(item);
+        Attributes::invalidate(&mut data.attributes, entity);
+        if entity == *data.player_entity {
+            let equipped_name = data.names.get(item).unwrap();
+            data.gamelog
+                .entries
+                .insert(0, format!("You equip {}.", equipped_name.name));
+        }
+        if let Some(cursed) = data.cursed.get_mut(item) {
+            cursed.revealed = true;
+            if entity == *data.player_entity {
+                let equipped_name = &data.names.get(item).unwrap().name;
+                data.gamelog
+                    .entries
+                    .insert(0, format!("The {} is cursed! It binds itself to you.", equipped_name));
+            }
+        }
     }
 }
 
@@ -96,78 +193,37 @@ impl<'a> System<'a> for ItemUseSystem {
     type SystemData = ItemUseSystemData<'a>;
 
     fn run(&mut self, mut data: Self::SystemData) {
-        for (entity, used_item, item_name) in (&data.entities, &data.wants_to_use_item, &data.names).join() {
-            let mut used = false;
-            let action_targets = Self::targets(used_item, &data);
-
-            // TODO: SPLIT IT TO SEPARATE SYUSTEMS
-            match data.provides_healing.get(used_item.item) {
-                None => {}
-                Some(healing) => {
-                    for target in action_targets.iter() {
-                        if let Some(stats) = data.combat_stats.get_mut(*target) {
-                            stats.hp = i32::min(stats.max_hp, stats.hp + healing.heal_amount);
-                            if entity == *data.player_entity {
-                                data.gamelog.entries.insert(
-                                    0,
-                                    format!("You use the {}, healing {}", item_name.name, healing.heal_amount),
-                                )
-                            }
-                        }
-                    }
-                    used = true;
-                }
-            }
-            match data.inflicts_damage.get(used_item.item) {
-                None => {}
-                Some(damage) => {
-                    for target in action_targets.iter() {
-                        data.suffer_damage
-                            .insert(*target, SufferDamage { amount: damage.damage })
-                            .expect("Unable to insert damage to target entity");
-                        if entity == *data.player_entity {
-                            let target_name = data.names.get(*target).unwrap();
-                            data.gamelog.entries.insert(
-                                0,
-                                format!(
-                                    "You use {} on {}, inflicting {} hp.",
-                                    item_name.name, target_name.name, damage.damage
-                                ),
-                            );
-                        }
-                    }
-                    used = true;
+        let uses: Vec<(Entity, WantsToUseItem)> = (&data.entities, &data.wants_to_use_item)
+            .join()
+            .map(|(entity, used_item)| (entity, used_item.clone()))
+            .collect();
+        for (entity, used_item) in uses.iter() {
+            let entity = *entity;
+            if matches!(data.charges.get(used_item.item), Some(charges) if charges.current <= 0) {
+                if entity == *data.player_entity {
+                    let item_name = &data.names.get(used_item.item).unwrap().name;
+                    data.gamelog
+                        .entries
+                        .insert(0, format!("The {} is out of charges.", item_name));
                 }
+                continue;
             }
-            let mut add_confusion = Vec::new();
-            match data.confusion.get(used_item.item) {
-                None => {}
-                Some(confuses) => {
-                    for target in action_targets.iter() {
-                        add_confusion.push((*target, confuses.turns));
-                        if entity == *data.player_entity {
-                            let target_name = data.names.get(*target).unwrap();
-                            data.gamelog.entries.insert(
-                                0,
-                                format!("You use {} on {}, confusing it.", item_name.name, target_name.name),
-                            );
-                        }
-                    }
-                    used = true;
+
+            if entity == *data.player_entity {
+                let real_name = &data.names.get(used_item.item).unwrap().name;
+                if data.identification.identify(real_name) {
+                    data.gamelog
+                        .entries
+                        .insert(0, format!("You identify the {}.", real_name));
                 }
             }
-            for (entity, turns) in add_confusion.iter() {
-                data.confusion
-                    .insert(*entity, Confusion { turns: *turns })
-                    .expect("Unable to insert confusion status");
-            }
-            if used {
-                match data.consumables.get(used_item.item) {
-                    None => {}
-                    Some(_) => data
-                        .entities
-                        .delete(used_item.item)
-                        .expect("Entity removal for consumables failed"),
+
+            match data.equippable.get(used_item.item) {
+                Some(equippable) => Self::equip(entity, used_item.item, equippable.slot, &mut data),
+                None => {
+                    let targets = Self::targets(entity, used_item, &data);
+                    data.effect_queue
+                        .add_effect(Some(entity), EffectType::ItemUse { item: used_item.item }, targets);
                 }
             }
         }
@@ -185,12 +241,36 @@ impl<'a> System<'a> for ItemDropSystem {
         ReadStorage<'a, Name>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, InBackpack>,
+        ReadExpect<'a, Identification>,
+        ReadStorage<'a, ObfuscatedName>,
+        ReadStorage<'a, Cursed>,
+        ReadStorage<'a, Equipped>,
     );
     fn run(&mut self, system_data: Self::SystemData) {
-        let (player_entity, mut gamelog, entities, mut wants_to_drop_item, names, mut positions, mut in_backpack) =
-            system_data;
+        let (
+            player_entity,
+            mut gamelog,
+            entities,
+            mut wants_to_drop_item,
+            names,
+            mut positions,
+            mut in_backpack,
+            identification,
+            obfuscated_names,
+            cursed,
+            equipped,
+        ) = system_data;
 
-        for (entity, to_drop, name) in (&entities, &wants_to_drop_item, &names).join() {
+        for (entity, to_drop) in (&entities, &wants_to_drop_item).join() {
+            if cursed.get(to_drop.item).is_some() && equipped.get(to_drop.item).is_some() {
+                if entity == *player_entity {
+                    let item_name = identification.display_name(to_drop.item, &names, &obfuscated_names);
+                    gamelog
+                        .entries
+                        .insert(0, format!("You can't let go of the cursed {}!", item_name));
+                }
+                continue;
+            }
             let position = *positions.get(entity).unwrap();
 
             positions
@@ -200,9 +280,67 @@ impl<'a> System<'a> for ItemDropSystem {
             in_backpack.remove(to_drop.item);
 
             if entity == *player_entity {
-                gamelog.entries.insert(0, format!("You dropped the {}.", name.name));
+                let item_name = identification.display_name(to_drop.item, &names, &obfuscated_names);
+                gamelog.entries.insert(0, format!("You dropped the {}.", item_name));
             }
         }
         wants_to_drop_item.clear();
     }
 }
+
+impl<'a> System<'a> for ItemRemoveSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, GameLog>,
+        Entities<'a>,
+        WriteStorage<'a, WantsToRemoveItem>,
+        ReadStorage<'a, Name>,
+        WriteStorage<'a, Equipped>,
+        WriteStorage<'a, InBackpack>,
+        WriteStorage<'a, Cursed>,
+        WriteStorage<'a, Attributes>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            player_entity,
+            mut gamelog,
+            entities,
+            mut wants_to_remove_item,
+            names,
+            mut equipped,
+            mut in_backpack,
+            mut cursed,
+            mut attributes,
+        ) = data;
+
+        for (entity, to_remove) in (&entities, &wants_to_remove_item).join() {
+            Attributes::invalidate(&mut attributes, entity);
+            if let Some(curse) = cursed.get_mut(to_remove.item) {
+                curse.revealed = true;
+                if entity == *player_entity {
+                    gamelog.entries.insert(
+                        0,
+                        format!(
+                            "You can't remove the cursed {}!",
+                            names.get(to_remove.item).unwrap().name
+                        ),
+                    );
+                }
+                continue;
+            }
+            equipped.remove(to_remove.item);
+            in_backpack
+                .insert(to_remove.item, InBackpack { owner: entity })
+                .expect("Unable to insert removed item to backpack");
+
+            if entity == *player_entity {
+                gamelog
+                    .entries
+                    .insert(0, format!("You unequip {}.", names.get(to_remove.item).unwrap().name));
+            }
+        }
+        wants_to_remove_item.clear();
+    }
+}
@@ -1,53 +1,165 @@
-use crate::components::{CombatStats, Name, SufferDamage, WantsToMelee};
+use crate::components::{
+    Ai, AiState, Burden, CombatStats, Cursed, DamageType, DefenseBonus, DerivedStats, EquipmentSlot, Equipped,
+    MeleeAttack, MeleePowerBonus, Name, Position, SufferDamage, WantsToMelee,
+};
 use crate::game_log::GameLog;
+use crate::noise::Noises;
+use rltk::{Point, RandomNumberGenerator};
 use specs::prelude::*;
 use std::cmp::max;
 
 pub struct MeleeCombatSystem {}
 
 static DEBUG_NAME: &str = "DEBUG: MISSING NAME";
+const BASE_ARMOR_CLASS: i32 = 10;
+const COMBAT_NOISE: i32 = 8;
+const OVERBURDENED_TO_HIT_PENALTY: i32 = 4;
+const UNARMED: MeleeAttack = MeleeAttack {
+    damage_n_dice: 1,
+    damage_die_type: 3,
+    damage_bonus: 0,
+    damage_type: DamageType::Bludgeoning,
+};
+
+#[derive(SystemData)]
+pub struct MeleeCombatSystemData<'a> {
+    entities: Entities<'a>,
+    wants_to_melee: WriteStorage<'a, WantsToMelee>,
+    names: ReadStorage<'a, Name>,
+    combat_stats: ReadStorage<'a, CombatStats>,
+    derived_stats: ReadStorage<'a, DerivedStats>,
+    melee_attacks: ReadStorage<'a, MeleeAttack>,
+    equipped: ReadStorage<'a, Equipped>,
+    melee_power_bonuses: ReadStorage<'a, MeleePowerBonus>,
+    defense_bonuses: ReadStorage<'a, DefenseBonus>,
+    cursed: ReadStorage<'a, Cursed>,
+    inflict_damage: WriteStorage<'a, SufferDamage>,
+    log: WriteExpect<'a, GameLog>,
+    rng: WriteExpect<'a, RandomNumberGenerator>,
+    noises: WriteExpect<'a, Noises>,
+    positions: ReadStorage<'a, Position>,
+    ais: ReadStorage<'a, Ai>,
+    burdens: ReadStorage<'a, Burden>,
+}
+
+impl MeleeCombatSystem {
+    fn power_bonus(owner: Entity, data: &MeleeCombatSystemData) -> i32 {
+        let bonus: i32 = (&data.equipped, &data.melee_power_bonuses)
+            .join()
+            .filter(|(equipped, _)| equipped.owner == owner)
+            .map(|(_, bonus)| bonus.power)
+            .sum();
+        let penalty: i32 = (&data.equipped, &data.cursed)
+            .join()
+            .filter(|(equipped, _)| equipped.owner == owner)
+            .map(|(_, cursed)| cursed.power_penalty)
+            .sum();
+        bonus - penalty
+    }
+
+    fn defense_bonus(owner: Entity, data: &MeleeCombatSystemData) -> i32 {
+        let bonus: i32 = (&data.equipped, &data.defense_bonuses)
+            .join()
+            .filter(|(equipped, _)| equipped.owner == owner)
+            .map(|(_, bonus)| bonus.defense)
+            .sum();
+        let penalty: i32 = (&data.equipped, &data.cursed)
+            .join()
+            .filter(|(equipped, _)| equipped.owner == owner)
+            .map(|(_, cursed)| cursed.defense_penalty)
+            .sum();
+        bonus - penalty
+    }
+
+    fn attack<'b>(owner: Entity, data: &'b MeleeCombatSystemData) -> &'b MeleeAttack {
+        (&data.equipped, &data.melee_attacks)
+            .join()
+            .find(|(equipped, _)| equipped.owner == owner && equipped.slot == EquipmentSlot::Melee)
+            .map(|(_, attack)| attack)
+            .or_else(|| data.melee_attacks.get(owner))
+            .unwrap_or(&UNARMED)
+    }
+}
 
 impl<'a> System<'a> for MeleeCombatSystem {
-    #[allow(clippy::type_complexity)]
-    type SystemData = (
-        Entities<'a>,
-        WriteStorage<'a, WantsToMelee>,
-        ReadStorage<'a, Name>,
-        ReadStorage<'a, CombatStats>,
-        WriteStorage<'a, SufferDamage>,
-        WriteExpect<'a, GameLog>,
-    );
-
-    fn run(&mut self, data: Self::SystemData) {
-        let (entities, mut wants_to_melee, names, combat_stats, mut inflict_damage, mut log) = data;
-
-        for (_entity, wants_to_melee, name, attacker_stats) in
-            (&entities, &wants_to_melee, &names, &combat_stats).join()
+    type SystemData = MeleeCombatSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        let mut hits = Vec::new();
+
+        for (entity, wants_to_melee, name, attacker_stats) in
+            (&data.entities, &data.wants_to_melee, &data.names, &data.combat_stats).join()
         {
             if attacker_stats.hp > 0 {
-                let target_stats = combat_stats.get(wants_to_melee.target).unwrap();
+                let target_stats = data.combat_stats.get(wants_to_melee.target).unwrap();
                 if target_stats.hp > 0 {
-                    let target_name = names.get(wants_to_melee.target);
-
-                    let damage = max(0, attacker_stats.power - target_stats.defense);
+                    let target_name = data.names.get(wants_to_melee.target);
                     let victim_name = match target_name {
                         Some(name) => &name.name,
                         None => DEBUG_NAME,
                     };
-                    if damage == 0 {
-                        log.entries
-                            .insert(0, format!("{} is unable to hurt {}", &name.name, victim_name))
+
+                    let mut to_hit = data.derived_stats.get(entity).map_or(0, |derived| derived.to_hit);
+                    if matches!(data.burdens.get(entity), Some(burden) if burden.overburdened()) {
+                        to_hit -= OVERBURDENED_TO_HIT_PENALTY;
+                    }
+                    let attack = Self::attack(entity, &data).clone();
+                    let armor_class =
+                        BASE_ARMOR_CLASS + target_stats.defense + Self::defense_bonus(wants_to_melee.target, &data);
+
+                    let natural_roll = data.rng.roll_dice(1, 20);
+                    let attack_roll = natural_roll + to_hit + attacker_stats.power + Self::power_bonus(entity, &data);
+                    let unaware = matches!(data.ais.get(wants_to_melee.target), Some(ai) if ai.state == AiState::Sleep);
+
+                    if let Some(pos) = data.positions.get(entity) {
+                        data.noises.emit(Point::new(pos.x, pos.y), COMBAT_NOISE);
+                    }
+
+                    if unaware {
+                        let damage = max(
+                            1,
+                            data.rng.roll_dice(attack.damage_n_dice * 2, attack.damage_die_type) + attack.damage_bonus,
+                        );
+                        data.log.entries.insert(
+                            0,
+                            format!("{} catches {} unaware, for {} hp!", &name.name, victim_name, damage),
+                        );
+                        hits.push((wants_to_melee.target, damage, attack.damage_type));
+                    } else if natural_roll == 1 {
+                        data.log
+                            .entries
+                            .insert(0, format!("{} fumbles the attack on {}!", &name.name, victim_name));
+                    } else if natural_roll == 20 {
+                        let damage = max(
+                            1,
+                            data.rng.roll_dice(attack.damage_n_dice * 2, attack.damage_die_type) + attack.damage_bonus,
+                        );
+                        data.log.entries.insert(
+                            0,
+                            format!("{} critically hits {}, for {} hp!", &name.name, victim_name, damage),
+                        );
+                        hits.push((wants_to_melee.target, damage, attack.damage_type));
+                    } else if attack_roll < armor_class {
+                        data.log
+                            .entries
+                            .insert(0, format!("{} misses {}.", &name.name, victim_name));
                     } else {
-                        log.entries
+                        let damage = max(
+                            1,
+                            data.rng.roll_dice(attack.damage_n_dice, attack.damage_die_type) + attack.damage_bonus,
+                        );
+                        data.log
+                            .entries
                             .insert(0, format!("{} hits {}, for {} hp.", &name.name, victim_name, damage));
-                        inflict_damage
-                            .insert(wants_to_melee.target, SufferDamage { amount: damage })
-                            .expect("Could not inflict damage");
+                        hits.push((wants_to_melee.target, damage, attack.damage_type));
                     }
                 }
             }
         }
 
-        wants_to_melee.clear();
+        for (target, amount, damage_type) in hits {
+            SufferDamage::new_damage(&mut data.inflict_damage, target, amount, damage_type);
+        }
+        data.wants_to_melee.clear();
     }
 }
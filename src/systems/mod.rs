@@ -1,12 +1,38 @@
+mod achievement_system;
+mod attribute_system;
 mod damage_system;
+mod effect_system;
+mod encumbrance_system;
+mod event_log_system;
+mod hearing_system;
+mod hunger_system;
+mod initiative_system;
 mod inventory_management;
 mod map_indexing_system;
 mod melee_combat_system;
 mod monster_ai_system;
+mod regeneration_system;
+mod sound_system;
+mod statistics_system;
+mod status_effect_system;
+mod throw_system;
 mod visibility_system;
+pub use achievement_system::AchievementSystem;
+pub use attribute_system::AttributeSystem;
 pub use damage_system::DamageSystem;
-pub use inventory_management::{ItemCollectionSystem, ItemDropSystem, ItemUseSystem};
+pub use effect_system::EffectSystem;
+pub use encumbrance_system::EncumbranceSystem;
+pub use event_log_system::EventLogSystem;
+pub use hearing_system::HearingSystem;
+pub use hunger_system::HungerSystem;
+pub use initiative_system::InitiativeSystem;
+pub use inventory_management::{ItemCollectionSystem, ItemDropSystem, ItemRemoveSystem, ItemUseSystem};
 pub use map_indexing_system::MapIndexingSystem;
 pub use melee_combat_system::MeleeCombatSystem;
 pub use monster_ai_system::MonsterAI;
+pub use regeneration_system::RegenerationSystem;
+pub use sound_system::SoundSystem;
+pub use statistics_system::StatisticsSystem;
+pub use status_effect_system::StatusEffectSystem;
+pub use throw_system::ThrowSystem;
 pub use visibility_system::VisibilitySystem;
@@ -1,64 +1,568 @@
-use crate::components::{Monster, Position, Viewshed, WantsToMelee};
-use crate::map::Map;
-use crate::state::RunState;
-use rltk::Point;
+use crate::components::{
+    Ability, Ai, AiState, AreaOfEffect, Charges, CombatStats, DerivedStats, Energy, Faction, InBackpack,
+    InflictsStatus, Item, ItemUser, Monster, MyTurn, Name, ObfuscatedName, PackFollower, PackLeader, Position,
+    ProvidesHealing, Ranged, StatusEffect, StatusEffectType, Viewshed, WantsToMelee, WantsToPickupItem, WantsToUseItem,
+};
+use crate::factions::{FactionTable, Reaction};
+use crate::game_log::GameLog;
+use crate::identification::Identification;
+use crate::map::{Map, TileType};
+use rltk::{DistanceAlg, LineAlg, Point, RandomNumberGenerator};
 use specs::prelude::*;
+use std::collections::HashMap;
 
 pub struct MonsterAI {}
 
-impl<'a> System<'a> for MonsterAI {
-    #[allow(clippy::type_complexity)]
-    type SystemData = (
-        WriteExpect<'a, Map>,
-        ReadExpect<'a, Point>,
-        ReadExpect<'a, Entity>,
-        ReadExpect<'a, RunState>,
-        Entities<'a>,
-        WriteStorage<'a, Viewshed>,
-        ReadStorage<'a, Monster>,
-        WriteStorage<'a, Position>,
-        WriteStorage<'a, WantsToMelee>,
-    );
-
-    fn run(&mut self, data: Self::SystemData) {
-        let (
-            mut map,
-            player_pos,
-            player_entity,
-            runstate,
-            entities,
-            mut viewsheds,
-            monsters,
-            mut positions,
-            mut wants_to_melee,
-        ) = data;
-
-        if *runstate == RunState::MonsterTurn {
-            for (entity, mut viewshed, _monster, mut pos) in
-                (&entities, &mut viewsheds, &monsters, &mut positions).join()
+const FLEE_THRESHOLD: f32 = 0.25;
+const WANDER_RADIUS: i32 = 8;
+const NOTICE_DIFFICULTY: i32 = 10;
+const PACK_RADIUS: f32 = 3.0;
+const MAX_DETOUR: usize = 4;
+
+#[derive(SystemData)]
+pub struct MonsterAISystemData<'a> {
+    map: WriteExpect<'a, Map>,
+    faction_table: ReadExpect<'a, FactionTable>,
+    rng: WriteExpect<'a, RandomNumberGenerator>,
+    entities: Entities<'a>,
+    viewsheds: WriteStorage<'a, Viewshed>,
+    monsters: ReadStorage<'a, Monster>,
+    positions: WriteStorage<'a, Position>,
+    wants_to_melee: WriteStorage<'a, WantsToMelee>,
+    status_effects: ReadStorage<'a, StatusEffect>,
+    my_turns: WriteStorage<'a, MyTurn>,
+    energies: WriteStorage<'a, Energy>,
+    ais: WriteStorage<'a, Ai>,
+    combat_stats: ReadStorage<'a, CombatStats>,
+    names: ReadStorage<'a, Name>,
+    factions: ReadStorage<'a, Faction>,
+    abilities: WriteStorage<'a, Ability>,
+    ranged: ReadStorage<'a, Ranged>,
+    wants_to_use_item: WriteStorage<'a, WantsToUseItem>,
+    gamelog: WriteExpect<'a, GameLog>,
+    item_users: ReadStorage<'a, ItemUser>,
+    items: ReadStorage<'a, Item>,
+    in_backpack: ReadStorage<'a, InBackpack>,
+    provides_healing: ReadStorage<'a, ProvidesHealing>,
+    inflicts_status: ReadStorage<'a, InflictsStatus>,
+    area_of_effect: ReadStorage<'a, AreaOfEffect>,
+    wants_to_pickup: WriteStorage<'a, WantsToPickupItem>,
+    derived_stats: ReadStorage<'a, DerivedStats>,
+    pack_leaders: ReadStorage<'a, PackLeader>,
+    pack_followers: ReadStorage<'a, PackFollower>,
+    identification: ReadExpect<'a, Identification>,
+    obfuscated_names: ReadStorage<'a, ObfuscatedName>,
+    charges: ReadStorage<'a, Charges>,
+}
+
+impl MonsterAI {
+    fn move_to(map: &mut Map, pos: &mut Position, viewshed: &mut Viewshed, destination: Point) -> bool {
+        let idx = map.xy_to_idx(destination.x, destination.y);
+        if map.blocked[idx] {
+            return false;
+        }
+        let old_idx = map.xy_to_idx(pos.x, pos.y);
+        map.blocked[old_idx] = false;
+        pos.x = destination.x;
+        pos.y = destination.y;
+        map.blocked[idx] = true;
+        viewshed.dirty = true;
+        true
+    }
+
+    fn path_to(map: &mut Map, from: Point, target: Point) -> Option<Vec<usize>> {
+        let target_idx = map.point_to_idx(target);
+        let target_blocked = map.blocked[target_idx];
+        map.blocked[target_idx] = false;
+        let path = rltk::a_star_search(map.point_to_idx(from) as i32, target_idx as i32, &*map);
+        map.blocked[target_idx] = target_blocked;
+
+        if path.success && path.steps.len() > 1 {
+            Some(path.steps)
+        } else {
+            None
+        }
+    }
+
+    fn step_towards(map: &mut Map, pos: &mut Position, viewshed: &mut Viewshed, target: Point) -> bool {
+        match Self::path_to(map, Point::new(pos.x, pos.y), target) {
+            Some(steps) => {
+                let (x, y) = map.idx_to_xy(steps[1]);
+                Self::move_to(map, pos, viewshed, Point::new(x, y))
+            }
+            None => false,
+        }
+    }
+
+    fn surround(map: &mut Map, pos: &mut Position, viewshed: &mut Viewshed, target: Point) -> bool {
+        let here = Point::new(pos.x, pos.y);
+        let mut best: Option<(Point, f32)> = None;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let candidate = Point::new(target.x + dx, target.y + dy);
+                if candidate == target || map.blocked[map.point_to_idx(candidate)] {
+                    continue;
+                }
+                let distance = DistanceAlg::Pythagoras.distance2d(here, candidate);
+                match best {
+                    Some((_, closest)) if closest <= distance => {}
+                    _ => best = Some((candidate, distance)),
+                }
+            }
+        }
+
+        let (flank, distance) = match best {
+            Some(best) => best,
+            None => return false,
+        };
+        match Self::path_to(map, here, flank) {
+            Some(steps) if steps.len() <= distance as usize * 2 + MAX_DETOUR => {
+                let (x, y) = map.idx_to_xy(steps[1]);
+                Self::move_to(map, pos, viewshed, Point::new(x, y))
+            }
+            _ => false,
+        }
+    }
+
+    fn ally_ahead(entity: Entity, here: Point, target: Point, map: &Map, factions: &ReadStorage<Faction>) -> bool {
+        let faction = match factions.get(entity) {
+            Some(faction) => &faction.name,
+            None => return false,
+        };
+        let distance = DistanceAlg::Pythagoras.distance2d(here, target);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let candidate = Point::new(here.x + dx, here.y + dy);
+                let idx = map.point_to_idx(candidate);
+                if !map.blocked[idx] || DistanceAlg::Pythagoras.distance2d(candidate, target) >= distance {
+                    continue;
+                }
+                let ally = map.tile_content[idx].iter().any(|other| {
+                    *other != entity && matches!(factions.get(*other), Some(other) if other.name == *faction)
+                });
+                if ally {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    fn step_away(map: &mut Map, pos: &mut Position, viewshed: &mut Viewshed, threat: Point) -> bool {
+        let here = Point::new(pos.x, pos.y);
+        let mut best = (here, DistanceAlg::Pythagoras.distance2d(here, threat));
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let candidate = Point::new(pos.x + dx, pos.y + dy);
+                let idx = map.xy_to_idx(candidate.x, candidate.y);
+                if map.blocked[idx] {
+                    continue;
+                }
+                let distance = DistanceAlg::Pythagoras.distance2d(candidate, threat);
+                if distance > best.1 {
+                    best = (candidate, distance);
+                }
+            }
+        }
+        best.0 != here && Self::move_to(map, pos, viewshed, best.0)
+    }
+
+    fn random_destination(map: &Map, rng: &mut RandomNumberGenerator, pos: &Position) -> Option<Point> {
+        let x = pos.x + rng.range(-WANDER_RADIUS, WANDER_RADIUS + 1);
+        let y = pos.y + rng.range(-WANDER_RADIUS, WANDER_RADIUS + 1);
+        if x < 1 || x > map.width - 2 || y < 1 || y > map.height - 2 {
+            return None;
+        }
+        let idx = map.xy_to_idx(x, y);
+        if map.tiles[idx] == TileType::Floor {
+            Some(Point::new(x, y))
+        } else {
+            None
+        }
+    }
+
+    fn choose_target(
+        entity: Entity,
+        here: Point,
+        viewshed: &Viewshed,
+        map: &Map,
+        faction_table: &FactionTable,
+        factions: &ReadStorage<Faction>,
+        combat_stats: &ReadStorage<CombatStats>,
+    ) -> Option<(Entity, Point, Reaction)> {
+        let faction = &factions.get(entity)?.name;
+        let mut threat: Option<(Entity, Point, f32)> = None;
+        let mut prey: Option<(Entity, Point, f32)> = None;
+
+        for tile in viewshed.visible_tiles.iter() {
+            let idx = map.point_to_idx(*tile);
+            for other in map.tile_content[idx].iter() {
+                if *other == entity || combat_stats.get(*other).is_none() {
+                    continue;
+                }
+                let other_faction = match factions.get(*other) {
+                    Some(other_faction) => other_faction,
+                    None => continue,
+                };
+                let distance = DistanceAlg::Pythagoras.distance2d(here, *tile);
+                let closest = match faction_table.reaction(faction, &other_faction.name) {
+                    Reaction::Flee => &mut threat,
+                    Reaction::Attack => &mut prey,
+                    Reaction::Ignore => continue,
+                };
+                match closest {
+                    Some((_, _, best)) if *best <= distance => {}
+                    _ => *closest = Some((*other, *tile, distance)),
+                }
+            }
+        }
+
+        threat
+            .map(|(other, point, _)| (other, point, Reaction::Flee))
+            .or_else(|| prey.map(|(other, point, _)| (other, point, Reaction::Attack)))
+    }
+
+    fn clear_shot(map: &Map, from: Point, to: Point) -> bool {
+        let line = rltk::line2d(LineAlg::Bresenham, from, to);
+        line.iter()
+            .filter(|point| **point != from && **point != to)
+            .all(|point| !map.blocked[map.point_to_idx(*point)])
+    }
+
+    fn ready_ability(
+        entity: Entity,
+        distance: f32,
+        entities: &Entities,
+        abilities: &WriteStorage<Ability>,
+        ranged: &ReadStorage<Ranged>,
+    ) -> Option<Entity> {
+        (entities, abilities, ranged)
+            .join()
+            .find(|(_, ability, ranged)| {
+                ability.owner == entity && ability.turns_until_ready <= 0 && ranged.range as f32 >= distance
+            })
+            .map(|(ability_entity, _, _)| ability_entity)
+    }
+
+    fn healing_item(
+        backpack: &[Entity],
+        provides_healing: &ReadStorage<ProvidesHealing>,
+        inflicts_status: &ReadStorage<InflictsStatus>,
+    ) -> Option<Entity> {
+        backpack.iter().copied().find(|item| {
+            provides_healing.get(*item).is_some()
+                || matches!(inflicts_status.get(*item), Some(status) if status.effect_type == StatusEffectType::Regenerating)
+        })
+    }
+
+    fn offensive_item(
+        backpack: &[Entity],
+        distance: f32,
+        ranged: &ReadStorage<Ranged>,
+        area_of_effect: &ReadStorage<AreaOfEffect>,
+        charges: &ReadStorage<Charges>,
+    ) -> Option<Entity> {
+        backpack.iter().copied().find(|item| {
+            matches!(ranged.get(*item), Some(ranged) if ranged.range as f32 >= distance)
+                && !matches!(charges.get(*item), Some(charges) if charges.current <= 0)
+                && !matches!(area_of_effect.get(*item), Some(aoe) if aoe.radius as f32 >= distance)
+        })
+    }
+
+    fn nearest_item(map: &Map, here: Point, viewshed: &Viewshed, items: &ReadStorage<Item>) -> Option<(Entity, Point)> {
+        let mut nearest: Option<(Entity, Point, f32)> = None;
+        for tile in viewshed.visible_tiles.iter() {
+            let idx = map.point_to_idx(*tile);
+            for item in map.tile_content[idx]
+                .iter()
+                .filter(|entity| items.get(**entity).is_some())
             {
-                let distance = rltk::DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), *player_pos);
-                if distance < 1.5 {
-                    wants_to_melee
-                        .insert(entity, WantsToMelee { target: *player_entity })
-                        .expect("Unable to insert attack");
-                } else if viewshed.visible_tiles.contains(&*player_pos) {
-                    let path = rltk::a_star_search(
-                        map.xy_to_idx(pos.x, pos.y) as i32,
-                        map.xy_to_idx(player_pos.x, player_pos.y) as i32,
-                        &*map,
-                    );
-
-                    if path.success && path.steps.len() > 1 {
-                        let mut idx = map.xy_to_idx(pos.x, pos.y);
-                        map.blocked[idx] = false;
-                        pos.x = path.steps[1] as i32 % map.width;
-                        pos.y = path.steps[1] as i32 / map.width;
-                        idx = map.xy_to_idx(pos.x, pos.y);
-                        map.blocked[idx] = true;
-                        viewshed.dirty = true;
+                let distance = DistanceAlg::Pythagoras.distance2d(here, *tile);
+                match nearest {
+                    Some((_, _, best)) if best <= distance => {}
+                    _ => nearest = Some((*item, *tile, distance)),
+                }
+            }
+        }
+        nearest.map(|(item, point, _)| (item, point))
+    }
+
+    fn notices(rng: &mut RandomNumberGenerator, here: Point, target_pos: Point, stealth: i32) -> bool {
+        DistanceAlg::Pythagoras.distance2d(here, target_pos) < 1.5
+            || rng.roll_dice(1, 20) >= NOTICE_DIFFICULTY + stealth
+    }
+}
+
+impl<'a> System<'a> for MonsterAI {
+    type SystemData = MonsterAISystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        let mut acted = Vec::new();
+        let mut orders: HashMap<Entity, (AiState, Option<Point>, Point)> = HashMap::new();
+        for (leader, _leader, ai, pos) in (&data.entities, &data.pack_leaders, &data.ais, &data.positions).join() {
+            orders.insert(leader, (ai.state, ai.last_seen_target, Point::new(pos.x, pos.y)));
+        }
+        let mut backpacks: HashMap<Entity, Vec<Entity>> = HashMap::new();
+        for (item, in_backpack) in (&data.entities, &data.in_backpack).join() {
+            backpacks.entry(in_backpack.owner).or_default().push(item);
+        }
+
+        for (entity, viewshed, _monster, pos, ai, _my_turn) in (
+            &data.entities,
+            &mut data.viewsheds,
+            &data.monsters,
+            &mut data.positions,
+            &mut data.ais,
+            &data.my_turns,
+        )
+            .join()
+        {
+            let map = &mut *data.map;
+            let name = data.names.get(entity);
+
+            for ability in (&mut data.abilities).join() {
+                if ability.owner == entity && ability.turns_until_ready > 0 {
+                    ability.turns_until_ready -= 1;
+                }
+            }
+
+            let order = data
+                .pack_followers
+                .get(entity)
+                .and_then(|follower| orders.get(&follower.leader))
+                .copied();
+            if let (AiState::Sleep, Some((AiState::Hunt, Some(rally), _))) = (ai.state, order) {
+                ai.last_seen_target = Some(rally);
+                ai.transition(AiState::Search, entity, name);
+            }
+
+            if ai.state == AiState::Sleep {
+                acted.push((entity, Energy::MOVE_COST));
+                continue;
+            }
+
+            if StatusEffect::is_affected(&data.status_effects, entity, StatusEffectType::Confused) {
+                let destination = Point::new(pos.x + data.rng.range(-1, 2), pos.y + data.rng.range(-1, 2));
+                Self::move_to(map, pos, viewshed, destination);
+                acted.push((entity, Energy::MOVE_COST));
+                continue;
+            }
+
+            let badly_hurt = matches!(
+                data.combat_stats.get(entity),
+                Some(stats) if (stats.hp as f32) < stats.max_hp as f32 * FLEE_THRESHOLD
+            );
+            let here = Point::new(pos.x, pos.y);
+            let visible = map.visible_tiles[map.point_to_idx(here)];
+            let backpack = backpacks.get(&entity).map_or(&[][..], |items| &items[..]);
+            let (ranged, area_of_effect, charges) = (&data.ranged, &data.area_of_effect, &data.charges);
+
+            let hurt = matches!(data.combat_stats.get(entity), Some(stats) if stats.hp < stats.max_hp / 2);
+            let potion = if hurt {
+                Self::healing_item(backpack, &data.provides_healing, &data.inflicts_status)
+            } else {
+                None
+            };
+            if let Some(potion) = potion {
+                data.wants_to_use_item
+                    .insert(
+                        entity,
+                        WantsToUseItem {
+                            item: potion,
+                            target: None,
+                        },
+                    )
+                    .expect("Unable to insert intent");
+                if let (true, Some(name)) = (visible, name) {
+                    let potion_name = data
+                        .identification
+                        .display_name(potion, &data.names, &data.obfuscated_names);
+                    data.gamelog
+                        .entries
+                        .insert(0, format!("{} drinks {}.", name.name, potion_name));
+                }
+                acted.push((entity, Energy::USE_ITEM_COST));
+                continue;
+            }
+
+            let mut target = Self::choose_target(
+                entity,
+                here,
+                viewshed,
+                map,
+                &data.faction_table,
+                &data.factions,
+                &data.combat_stats,
+            );
+            if let (AiState::Wander, Some((target_entity, target_pos, _))) = (ai.state, target) {
+                let stealth = data
+                    .derived_stats
+                    .get(target_entity)
+                    .map_or(0, |derived| derived.stealth);
+                if !Self::notices(&mut data.rng, here, target_pos, stealth) {
+                    target = None;
+                }
+            }
+
+            if let Some((_, target_pos, reaction)) = target {
+                ai.last_seen_target = Some(target_pos);
+                ai.destination = None;
+                let state = if reaction == Reaction::Flee || badly_hurt {
+                    AiState::Flee
+                } else {
+                    AiState::Hunt
+                };
+                ai.transition(state, entity, name);
+            } else if ai.state == AiState::Hunt {
+                ai.transition(AiState::Search, entity, name);
+            } else if ai.state == AiState::Flee {
+                ai.last_seen_target = None;
+                ai.transition(AiState::Wander, entity, name);
+            }
+
+            match (order, target) {
+                (Some((AiState::Flee, _, _)), Some(_)) => ai.transition(AiState::Flee, entity, name),
+                (Some((AiState::Hunt, Some(rally), _)), None)
+                    if ai.state == AiState::Wander || ai.state == AiState::Search =>
+                {
+                    ai.last_seen_target = Some(rally);
+                    ai.transition(AiState::Search, entity, name);
+                }
+                (Some((AiState::Wander, _, leader_pos)), None)
+                    if ai.state == AiState::Wander
+                        && DistanceAlg::Pythagoras.distance2d(here, leader_pos) > PACK_RADIUS =>
+                {
+                    ai.destination = Some(leader_pos);
+                }
+                _ => {}
+            }
+
+            let cost = match (ai.state, target) {
+                (AiState::Hunt, Some((target, target_pos, _))) => {
+                    let distance = DistanceAlg::Pythagoras.distance2d(here, target_pos);
+                    let ability = if distance >= 1.5 && Self::clear_shot(map, here, target_pos) {
+                        Self::ready_ability(entity, distance, &data.entities, &data.abilities, &data.ranged)
+                            .or_else(|| Self::offensive_item(backpack, distance, ranged, area_of_effect, charges))
+                    } else {
+                        None
+                    };
+
+                    if distance < 1.5 {
+                        data.wants_to_melee
+                            .insert(entity, WantsToMelee { target })
+                            .expect("Unable to insert attack");
+                        Energy::ATTACK_COST
+                    } else if let Some(ability) = ability {
+                        if let Some(ability) = data.abilities.get_mut(ability) {
+                            ability.turns_until_ready = ability.cooldown;
+                        }
+                        data.wants_to_use_item
+                            .insert(
+                                entity,
+                                WantsToUseItem {
+                                    item: ability,
+                                    target: Some(target_pos),
+                                },
+                            )
+                            .expect("Unable to insert intent");
+                        if let (true, Some(name), Some(target_name)) = (visible, name, data.names.get(target)) {
+                            let ability_name =
+                                data.identification
+                                    .display_name(ability, &data.names, &data.obfuscated_names);
+                            let verb = if data.abilities.get(ability).is_some() {
+                                "fires"
+                            } else {
+                                "uses"
+                            };
+                            data.gamelog.entries.insert(
+                                0,
+                                format!("{} {} {} at {}.", name.name, verb, ability_name, target_name.name),
+                            );
+                        }
+                        Energy::USE_ITEM_COST
+                    } else {
+                        if !Self::surround(map, pos, viewshed, target_pos)
+                            && !Self::ally_ahead(entity, here, target_pos, map, &data.factions)
+                        {
+                            Self::step_towards(map, pos, viewshed, target_pos);
+                        }
+                        Energy::MOVE_COST
+                    }
+                }
+                (AiState::Flee, Some((target, target_pos, _))) => {
+                    if !Self::step_away(map, pos, viewshed, target_pos)
+                        && DistanceAlg::Pythagoras.distance2d(here, target_pos) < 1.5
+                    {
+                        data.wants_to_melee
+                            .insert(entity, WantsToMelee { target })
+                            .expect("Unable to insert attack");
+                        Energy::ATTACK_COST
+                    } else {
+                        Energy::MOVE_COST
                     }
                 }
+                (AiState::Search, _) => {
+                    match ai.last_seen_target {
+                        Some(last_seen) if here != last_seen && Self::step_towards(map, pos, viewshed, last_seen) => {}
+                        _ => {
+                            ai.last_seen_target = None;
+                            ai.transition(AiState::Wander, entity, name);
+                        }
+                    }
+                    Energy::MOVE_COST
+                }
+                _ => {
+                    let item = if data.item_users.get(entity).is_some() {
+                        Self::nearest_item(map, here, viewshed, &data.items)
+                    } else {
+                        None
+                    };
+                    match item {
+                        Some((item, item_pos)) if item_pos == here => {
+                            data.wants_to_pickup
+                                .insert(
+                                    entity,
+                                    WantsToPickupItem {
+                                        collected_by: entity,
+                                        item,
+                                    },
+                                )
+                                .expect("Unable to insert want to pickup");
+                            if let (true, Some(name)) = (visible, name) {
+                                let item_name =
+                                    data.identification
+                                        .display_name(item, &data.names, &data.obfuscated_names);
+                                data.gamelog
+                                    .entries
+                                    .insert(0, format!("{} picks up {}.", name.name, item_name));
+                            }
+                            ai.destination = None;
+                            Energy::PICKUP_COST
+                        }
+                        _ => {
+                            if let Some((_, item_pos)) = item {
+                                ai.destination = Some(item_pos);
+                            } else if ai.destination.is_none() {
+                                ai.destination = Self::random_destination(map, &mut data.rng, pos);
+                            }
+                            if let Some(destination) = ai.destination {
+                                let arrived = pos.x == destination.x && pos.y == destination.y;
+                                if arrived || !Self::step_towards(map, pos, viewshed, destination) {
+                                    ai.destination = None;
+                                }
+                            }
+                            Energy::MOVE_COST
+                        }
+                    }
+                }
+            };
+            acted.push((entity, cost));
+        }
+
+        for (entity, cost) in acted {
+            data.my_turns.remove(entity);
+            if let Some(energy) = data.energies.get_mut(entity) {
+                energy.energy -= cost;
             }
         }
     }
@@ -0,0 +1,57 @@
+use crate::components::{CombatStats, Dead, DerivedStats, HungerClock, HungerState, MyTurn};
+use crate::effects::{EffectQueue, EffectType, Targets};
+use crate::state::RunState;
+use rltk::RandomNumberGenerator;
+use specs::prelude::*;
+
+pub struct RegenerationSystem {}
+
+const REGENERATION_AMOUNT: i32 = 1;
+
+#[derive(SystemData)]
+pub struct RegenerationSystemData<'a> {
+    runstate: ReadExpect<'a, RunState>,
+    rng: WriteExpect<'a, RandomNumberGenerator>,
+    effect_queue: WriteExpect<'a, EffectQueue>,
+    entities: Entities<'a>,
+    my_turns: ReadStorage<'a, MyTurn>,
+    combat_stats: ReadStorage<'a, CombatStats>,
+    derived_stats: ReadStorage<'a, DerivedStats>,
+    hunger_clocks: ReadStorage<'a, HungerClock>,
+    dead: ReadStorage<'a, Dead>,
+}
+
+impl<'a> System<'a> for RegenerationSystem {
+    type SystemData = RegenerationSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        if *data.runstate != RunState::Ticking && *data.runstate != RunState::AwaitingInput {
+            return;
+        }
+
+        for (entity, stats, derived, _my_turn, _alive) in (
+            &data.entities,
+            &data.combat_stats,
+            &data.derived_stats,
+            &data.my_turns,
+            !&data.dead,
+        )
+            .join()
+        {
+            let hungry = matches!(
+                data.hunger_clocks.get(entity),
+                Some(clock) if clock.state == HungerState::Hungry || clock.state == HungerState::Starving
+            );
+            if hungry || stats.hp >= stats.max_hp || data.rng.roll_dice(1, derived.regen_interval) != 1 {
+                continue;
+            }
+            data.effect_queue.add_effect(
+                None,
+                EffectType::Healing {
+                    amount: REGENERATION_AMOUNT,
+                },
+                Targets::Single { target: entity },
+            );
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use crate::events::{GameEvent, GameEvents};
+use rltk::console;
+use specs::prelude::*;
+
+pub struct SoundSystem {}
+
+impl<'a> System<'a> for SoundSystem {
+    type SystemData = ReadExpect<'a, GameEvents>;
+
+    fn run(&mut self, events: Self::SystemData) {
+        for event in events.events.iter() {
+            let cue = match event {
+                GameEvent::EntityDamaged { damage_type, .. } => format!("hit_{}", damage_type.name()),
+                GameEvent::EntityDied { .. } => "death".to_string(),
+                GameEvent::ItemPickedUp { .. } => "pickup".to_string(),
+                GameEvent::LevelEntered { .. } => "level".to_string(),
+            };
+            console::log(format!("sound cue: {}", cue));
+        }
+    }
+}
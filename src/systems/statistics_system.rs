@@ -0,0 +1,33 @@
+use crate::events::{GameEvent, GameEvents};
+use crate::statistics::Statistics;
+use specs::prelude::*;
+
+pub struct StatisticsSystem {}
+
+impl<'a> System<'a> for StatisticsSystem {
+    type SystemData = (
+        ReadExpect<'a, Entity>,
+        ReadExpect<'a, GameEvents>,
+        WriteExpect<'a, Statistics>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (player_entity, events, mut statistics) = data;
+
+        for event in events.events.iter() {
+            match event {
+                GameEvent::EntityDamaged { entity, amount, .. } if *entity == *player_entity => {
+                    statistics.damage_taken += amount
+                }
+                GameEvent::EntityDied { entity, .. } if *entity != *player_entity => statistics.kills += 1,
+                GameEvent::ItemPickedUp { collector, .. } if *collector == *player_entity => {
+                    statistics.items_picked_up += 1
+                }
+                GameEvent::LevelEntered { depth } => {
+                    statistics.deepest_level = i32::max(statistics.deepest_level, *depth)
+                }
+                _ => {}
+            }
+        }
+    }
+}
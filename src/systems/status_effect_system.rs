@@ -0,0 +1,97 @@
+use crate::components::{Attributes, DamageType, Energy, MyTurn, Name, StatusEffect, StatusEffectType};
+use crate::effects::{EffectQueue, EffectType, Targets};
+use crate::game_log::GameLog;
+use crate::state::RunState;
+use specs::prelude::*;
+
+pub struct StatusEffectSystem {}
+
+const POISON_DAMAGE: i32 = 1;
+const REGENERATION_AMOUNT: i32 = 1;
+
+#[derive(SystemData)]
+pub struct StatusEffectSystemData<'a> {
+    player_entity: ReadExpect<'a, Entity>,
+    runstate: WriteExpect<'a, RunState>,
+    gamelog: WriteExpect<'a, GameLog>,
+    entities: Entities<'a>,
+    status_effects: WriteStorage<'a, StatusEffect>,
+    effect_queue: WriteExpect<'a, EffectQueue>,
+    my_turns: WriteStorage<'a, MyTurn>,
+    energies: WriteStorage<'a, Energy>,
+    names: ReadStorage<'a, Name>,
+    attributes: WriteStorage<'a, Attributes>,
+}
+
+impl<'a> System<'a> for StatusEffectSystem {
+    type SystemData = StatusEffectSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        if *data.runstate != RunState::Ticking && *data.runstate != RunState::AwaitingInput {
+            return;
+        }
+
+        let mut stunned = Vec::new();
+        for (effect_entity, effect) in (&data.entities, &mut data.status_effects).join() {
+            if !data.entities.is_alive(effect.target) {
+                data.entities
+                    .delete(effect_entity)
+                    .expect("Unable to delete status effect");
+                continue;
+            }
+            if data.my_turns.get(effect.target).is_none() {
+                continue;
+            }
+
+            match effect.effect_type {
+                StatusEffectType::Poisoned => data.effect_queue.add_effect(
+                    None,
+                    EffectType::Damage {
+                        amount: POISON_DAMAGE,
+                        damage_type: DamageType::Poison,
+                    },
+                    Targets::Single { target: effect.target },
+                ),
+                StatusEffectType::Regenerating => data.effect_queue.add_effect(
+                    None,
+                    EffectType::Healing {
+                        amount: REGENERATION_AMOUNT,
+                    },
+                    Targets::Single { target: effect.target },
+                ),
+                StatusEffectType::Stunned => stunned.push(effect.target),
+                _ => {}
+            }
+
+            effect.turns -= 1;
+            if effect.turns < 1 {
+                data.entities
+                    .delete(effect_entity)
+                    .expect("Unable to delete status effect");
+                Attributes::invalidate(&mut data.attributes, effect.target);
+                if effect.target == *data.player_entity {
+                    data.gamelog
+                        .entries
+                        .insert(0, format!("You are no longer {}.", effect.effect_type.name()));
+                } else if let Some(name) = data.names.get(effect.target) {
+                    data.gamelog
+                        .entries
+                        .insert(0, format!("{} is no longer {}.", name.name, effect.effect_type.name()));
+                }
+            }
+        }
+
+        for target in stunned {
+            data.my_turns.remove(target);
+            if let Some(energy) = data.energies.get_mut(target) {
+                energy.energy -= Energy::ACTION_COST;
+            }
+            if target == *data.player_entity {
+                data.gamelog
+                    .entries
+                    .insert(0, "You are stunned and cannot act.".to_string());
+                *data.runstate = RunState::Ticking;
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use crate::components::{
+    AreaOfEffect, CombatStats, DamageType, InBackpack, MeleeAttack, Name, ObfuscatedName, Position, Renderable,
+    Shatters, WantsToThrowItem, WantsToUseItem,
+};
+use crate::effects::{EffectQueue, EffectType, Targets};
+use crate::game_log::GameLog;
+use crate::identification::Identification;
+use crate::map::{Map, TileType};
+use crate::projectile::Projectiles;
+use rltk::{LineAlg, Point, RandomNumberGenerator};
+use specs::prelude::*;
+
+pub struct ThrowSystem {}
+
+const SHATTER_RADIUS: i32 = 1;
+
+#[derive(SystemData)]
+pub struct ThrowSystemData<'a> {
+    player_entity: ReadExpect<'a, Entity>,
+    gamelog: WriteExpect<'a, GameLog>,
+    map: ReadExpect<'a, Map>,
+    projectiles: WriteExpect<'a, Projectiles>,
+    rng: WriteExpect<'a, RandomNumberGenerator>,
+    identification: ReadExpect<'a, Identification>,
+    entities: Entities<'a>,
+    wants_to_throw: WriteStorage<'a, WantsToThrowItem>,
+    wants_to_use_item: WriteStorage<'a, WantsToUseItem>,
+    positions: WriteStorage<'a, Position>,
+    in_backpack: WriteStorage<'a, InBackpack>,
+    names: ReadStorage<'a, Name>,
+    obfuscated_names: ReadStorage<'a, ObfuscatedName>,
+    renderables: ReadStorage<'a, Renderable>,
+    combat_stats: ReadStorage<'a, CombatStats>,
+    melee_attacks: ReadStorage<'a, MeleeAttack>,
+    shatters: ReadStorage<'a, Shatters>,
+    area_of_effect: WriteStorage<'a, AreaOfEffect>,
+    effect_queue: WriteExpect<'a, EffectQueue>,
+}
+
+impl ThrowSystem {
+    fn flight(
+        map: &Map,
+        combat_stats: &ReadStorage<CombatStats>,
+        from: Point,
+        target: Point,
+    ) -> (Vec<Point>, Option<Entity>) {
+        let mut path = vec![from];
+        for point in rltk::line2d(LineAlg::Bresenham, from, target).into_iter().skip(1) {
+            let idx = map.point_to_idx(point);
+            if map.tiles[idx] == TileType::Wall {
+                break;
+            }
+            path.push(point);
+            if let Some(victim) = map.tile_content[idx]
+                .iter()
+                .find(|entity| combat_stats.get(**entity).is_some())
+            {
+                return (path, Some(*victim));
+            }
+        }
+        (path, None)
+    }
+}
+
+impl<'a> System<'a> for ThrowSystem {
+    type SystemData = ThrowSystemData<'a>;
+
+    fn run(&mut self, mut data: Self::SystemData) {
+        let mut throws = Vec::new();
+        for (thrower, throw, pos) in (&data.entities, &data.wants_to_throw, &data.positions).join() {
+            throws.push((thrower, throw.item, Point::new(pos.x, pos.y), throw.target));
+        }
+
+        for (thrower, item, from, target) in throws {
+            let (path, victim) = Self::flight(&data.map, &data.combat_stats, from, target);
+            let landing = *path.last().unwrap();
+
+            data.in_backpack.remove(item);
+            data.positions
+                .insert(
+                    item,
+                    Position {
+                        x: landing.x,
+                        y: landing.y,
+                    },
+                )
+                .expect("Unable to place thrown item");
+            if let Some(render) = data.renderables.get(item) {
+                data.projectiles.launch(path, render.glyph, render.fg);
+            }
+
+            let thrower_name = data
+                .names
+                .get(thrower)
+                .map_or("Someone".to_string(), |name| name.name.clone());
+            let item_name = data
+                .identification
+                .display_name(item, &data.names, &data.obfuscated_names);
+            let visible = data.map.visible_tiles[data.map.point_to_idx(landing)];
+
+            if data.shatters.get(item).is_some() {
+                if data.area_of_effect.get(item).is_none() {
+                    data.area_of_effect
+                        .insert(item, AreaOfEffect { radius: SHATTER_RADIUS })
+                        .expect("Unable to insert shatter area");
+                }
+                data.wants_to_use_item
+                    .insert(
+                        thrower,
+                        WantsToUseItem {
+                            item,
+                            target: Some(landing),
+                        },
+                    )
+                    .expect("Unable to insert shatter effect");
+                if thrower == *data.player_entity || visible {
+                    data.gamelog.entries.insert(
+                        0,
+                        format!("{} throws the {}, and it shatters.", thrower_name, item_name),
+                    );
+                }
+            } else if let Some(victim) = victim {
+                let (damage, damage_type) = match data.melee_attacks.get(item) {
+                    Some(attack) => (
+                        i32::max(
+                            1,
+                            data.rng.roll_dice(attack.damage_n_dice, attack.damage_die_type) + attack.damage_bonus,
+                        ),
+                        attack.damage_type,
+                    ),
+                    None => (1, DamageType::Bludgeoning),
+                };
+                data.effect_queue.add_effect(
+                    Some(thrower),
+                    EffectType::Damage {
+                        amount: damage,
+                        damage_type,
+                    },
+                    Targets::Single { target: victim },
+                );
+                if thrower == *data.player_entity || visible {
+                    let victim_name = data.names.get(victim).map_or("something", |name| &name.name);
+                    data.gamelog.entries.insert(
+                        0,
+                        format!(
+                            "{} throws the {} at {}, for {} hp.",
+                            thrower_name, item_name, victim_name, damage
+                        ),
+                    );
+                }
+            } else if thrower == *data.player_entity {
+                data.gamelog.entries.insert(0, format!("You throw the {}.", item_name));
+            }
+        }
+
+        data.wants_to_throw.clear();
+    }
+}